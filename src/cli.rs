@@ -0,0 +1,73 @@
+use crate::export::Format;
+use crate::replay::MAX_SPEED;
+use crate::team::DEFAULT_PORT;
+use chrono::NaiveDate;
+use clap::{Parser, Subcommand};
+use std::path::PathBuf;
+
+#[derive(Parser, Clone)]
+#[command(version, about)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// Play, export or import as this player, created if needed. Skips the player picker
+    #[arg(long, global = true, value_name = "NAME")]
+    pub profile: Option<String>,
+
+    /// Word list for Verbal Memory, one word per line
+    #[arg(long, value_name = "FILE")]
+    pub word_list: Option<PathBuf>,
+}
+
+/// Things done without opening the games.
+#[derive(Subcommand, Clone)]
+pub enum Command {
+    /// Write the recorded sessions with every trial, for analysis elsewhere.
+    /// Uses the player played last unless --profile is given
+    Export {
+        #[arg(long, value_enum, default_value_t = Format::Csv)]
+        format: Format,
+        /// Only sessions of this game: reaction, verbal_memory or number_memory
+        #[arg(long)]
+        game: Option<String>,
+        /// Only sessions played on this day or later
+        #[arg(long, value_name = "YYYY-MM-DD")]
+        since: Option<NaiveDate>,
+        /// File to write, standard output by default
+        #[arg(long, short, value_name = "FILE")]
+        output: Option<PathBuf>,
+    },
+    /// Merge sessions exported on another machine into the history, skipping known ones
+    Import {
+        file: PathBuf,
+        /// Format of the file, guessed from its extension by default
+        #[arg(long, value_enum)]
+        format: Option<Format>,
+    },
+    /// Play a recorded game back, the latest one of the player by default. Esc stops it
+    Replay {
+        /// Replay file, from the replays folder of the player's data
+        file: Option<PathBuf>,
+        /// How many times faster than it was played, 0.5 slows it down
+        #[arg(long, default_value_t = 1.0, value_parser = parse_speed)]
+        speed: f64,
+    },
+    /// Host the team leaderboard for players on the LAN, until stopped with Ctrl+C
+    Serve {
+        /// Address to listen on, 127.0.0.1 keeps it to this machine
+        #[arg(long, default_value = "0.0.0.0")]
+        bind: String,
+        #[arg(long, default_value_t = DEFAULT_PORT)]
+        port: u16,
+    },
+}
+
+fn parse_speed(value: &str) -> Result<f64, String> {
+    let speed: f64 = value.parse().map_err(|_| format!("{value} is not a number"))?;
+    if speed > 0.0 && speed <= MAX_SPEED {
+        Ok(speed)
+    } else {
+        Err(format!("the speed goes from above 0 up to {MAX_SPEED}"))
+    }
+}
@@ -0,0 +1,125 @@
+use crate::history::{self, Ranking, SessionRecord};
+use crate::replay::{self, GameRng};
+use crate::storage;
+use chrono::{Local, NaiveDate};
+use ratatui::style::{Color, Style};
+use ratatui::text::{Line, Span};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Mode every game records its daily challenge under, ranked apart from free play.
+pub const MODE_ID: &str = "daily";
+/// Day each game's challenge was last started, per profile.
+const DAILY_FILE: &str = "daily.json";
+/// Best days listed with the daily status.
+const BEST_DAYS: usize = 3;
+
+/// Attempts used, one per game and day.
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+struct DailyLog {
+    started: BTreeMap<String, NaiveDate>,
+}
+
+fn today() -> NaiveDate {
+    Local::now().date_naive()
+}
+
+/// Today's random source for a game, the same on every machine running this version.
+pub fn rng(game: &str) -> GameRng {
+    GameRng::seeded(seed(game, today()))
+}
+
+/// FNV-1a of the day and the game, the standard hashers may change between Rust releases.
+fn seed(game: &str, day: NaiveDate) -> u64 {
+    format!("{day}/{game}")
+        .bytes()
+        .fold(0xcbf2_9ce4_8422_2325, |hash, byte| (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3))
+}
+
+/// Uses up today's attempt at the game, `false` if it already was.
+///
+/// Starting is what counts, so giving up early can't be used to preview the day's content.
+pub fn start(game: &str) -> bool {
+    // A replay plays the attempt that was already counted
+    if replay::is_playing() {
+        return true;
+    }
+    let mut log: DailyLog = storage::load(DAILY_FILE);
+    if log.started.get(game) == Some(&today()) {
+        return false;
+    }
+    log.started.insert(game.to_string(), today());
+    // The attempt is played anyway, it just can't be enforced next time
+    let _ = storage::save(DAILY_FILE, &log);
+    true
+}
+
+/// Where a player stands with the daily challenge of a game.
+pub struct DailyStatus {
+    /// Today's attempt is used, finished or not.
+    pub played: bool,
+    /// Score of today's run, once finished.
+    today: Option<u32>,
+    /// Days in a row the challenge was finished, up to today or yesterday.
+    streak: u32,
+    /// Best finished daily runs, best first.
+    best: Vec<SessionRecord>,
+}
+
+impl DailyStatus {
+    pub fn load(game: &str, ranking: Ranking) -> Self {
+        let log: DailyLog = storage::load(DAILY_FILE);
+        let now = today();
+        let mut runs: Vec<SessionRecord> = history::sessions()
+            .into_iter()
+            .filter(|session| !session.aborted && !session.imported)
+            .filter(|session| session.game == game && session.mode == MODE_ID)
+            .collect();
+
+        let days: BTreeSet<NaiveDate> = runs.iter().map(|run| run.played_at.date_naive()).collect();
+        // Today doesn't break the streak until it is over
+        let mut day = Some(now).filter(|day| days.contains(day)).or_else(|| now.pred_opt());
+        let mut streak = 0;
+        while let Some(current) = day.filter(|day| days.contains(day)) {
+            streak += 1;
+            day = current.pred_opt();
+        }
+
+        let today = runs.iter().find(|run| run.played_at.date_naive() == now).map(|run| run.score);
+        runs.sort_by(|a, b| ranking.compare(a.score, b.score).then(a.played_at.cmp(&b.played_at)));
+        runs.truncate(BEST_DAYS);
+
+        Self {
+            played: !replay::is_playing() && (log.started.get(game) == Some(&now) || today.is_some()),
+            today,
+            streak,
+            best: runs,
+        }
+    }
+
+    /// Today's challenge, the streak and the best days, for the title and end screens.
+    pub fn lines(&self, unit: &str, color: Color) -> Vec<Line<'static>> {
+        let style = Style::default().fg(color);
+        let headline = match (self.played, self.today) {
+            (_, Some(score)) => format!("📅 Today's challenge: {score} {unit} • next one tomorrow"),
+            (true, None) => "📅 Today's challenge was given up • next one tomorrow".to_string(),
+            (false, None) => "📅 t for today's challenge, the same for everyone, one attempt".to_string(),
+        };
+        let mut lines = vec![Line::from(Span::styled(headline, style))];
+
+        if self.streak > 0 {
+            let days = if self.streak == 1 { "day" } else { "days" };
+            lines.push(Line::from(Span::styled(format!("🔥 {} {days} in a row", self.streak), style)));
+        }
+        if !self.best.is_empty() {
+            let best: Vec<String> = self
+                .best
+                .iter()
+                .map(|run| format!("{} {unit} on {}", run.score, run.played_at.format("%Y-%m-%d")))
+                .collect();
+            lines.push(Line::from(Span::styled(format!("Best days: {}", best.join(" • ")), style)));
+        }
+        lines
+    }
+}
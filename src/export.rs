@@ -0,0 +1,235 @@
+use crate::history::{SessionRecord, Trial};
+use crate::storage;
+use chrono::{DateTime, Local, NaiveDate};
+use clap::ValueEnum;
+use std::collections::BTreeSet;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+
+/// Metric columns of the CSV format are named after the metric with this prefix.
+const METRIC_PREFIX: &str = "metric:";
+const SESSION_COLUMNS: [&str; 7] = ["session", "game", "mode", "score", "played_at", "aborted", "imported"];
+const TRIAL_COLUMNS: [&str; 5] = ["trial", "item", "answer", "correct", "ms"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    /// One row per trial, session columns repeated, for spreadsheets and dataframes
+    Csv,
+    /// An array of sessions with their trials nested, as stored in the history
+    Json,
+}
+
+impl Format {
+    /// Guesses the format of a file from its extension.
+    pub fn of(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()?.to_lowercase().as_str() {
+            "csv" => Some(Format::Csv),
+            "json" => Some(Format::Json),
+            _ => None,
+        }
+    }
+
+    fn extension(&self) -> &'static str {
+        match self {
+            Format::Csv => "csv",
+            Format::Json => "json",
+        }
+    }
+}
+
+/// Sessions of a game, or of every game, played on `since` or later.
+pub fn select(sessions: Vec<SessionRecord>, game: Option<&str>, since: Option<NaiveDate>) -> Vec<SessionRecord> {
+    sessions
+        .into_iter()
+        .filter(|session| game.is_none_or(|game| session.game == game))
+        .filter(|session| since.is_none_or(|since| session.played_at.date_naive() >= since))
+        .collect()
+}
+
+pub fn write<W: Write>(sessions: &[SessionRecord], format: Format, writer: W) -> io::Result<()> {
+    match format {
+        Format::Json => serde_json::to_writer_pretty(writer, sessions).map_err(io::Error::from),
+        Format::Csv => write_csv(sessions, writer),
+    }
+}
+
+pub fn read<R: Read>(format: Format, reader: R) -> io::Result<Vec<SessionRecord>> {
+    match format {
+        Format::Json => serde_json::from_reader(reader).map_err(io::Error::from),
+        Format::Csv => read_csv(reader),
+    }
+}
+
+/// Writes the sessions of a game into the exports folder of the profile, returns the file.
+pub fn export_to_profile_dir(sessions: &[SessionRecord], game: &str, format: Format) -> io::Result<PathBuf> {
+    let dir = storage::profile_dir().join("exports");
+    fs::create_dir_all(&dir)?;
+
+    let name = format!("{game}-{}.{}", Local::now().format("%Y%m%d-%H%M%S"), format.extension());
+    let path = dir.join(name);
+    write(sessions, format, fs::File::create(&path)?)?;
+    Ok(path)
+}
+
+fn write_csv<W: Write>(sessions: &[SessionRecord], writer: W) -> io::Result<()> {
+    let metrics: BTreeSet<&String> = sessions.iter().flat_map(|session| session.metrics.keys()).collect();
+
+    let mut csv = csv::Writer::from_writer(writer);
+    let header = SESSION_COLUMNS
+        .iter()
+        .map(|column| column.to_string())
+        .chain(metrics.iter().map(|name| format!("{METRIC_PREFIX}{name}")))
+        .chain(TRIAL_COLUMNS.iter().map(|column| column.to_string()));
+    csv.write_record(header)?;
+
+    let optional = |value: Option<String>| value.unwrap_or_default();
+    for (index, session) in sessions.iter().enumerate() {
+        let mut row = vec![
+            index.to_string(),
+            session.game.clone(),
+            session.mode.clone(),
+            session.score.to_string(),
+            session.played_at.to_rfc3339(),
+            session.aborted.to_string(),
+            session.imported.to_string(),
+        ];
+        row.extend(
+            metrics
+                .iter()
+                .map(|name| optional(session.metrics.get(*name).map(f64::to_string))),
+        );
+
+        // A session without trials still gets its row
+        if session.trials.is_empty() {
+            csv.write_record(row.iter().cloned().chain(TRIAL_COLUMNS.iter().map(|_| String::new())))?;
+        }
+        for (number, trial) in session.trials.iter().enumerate() {
+            let trial = [
+                number.to_string(),
+                optional(trial.item.clone()),
+                optional(trial.answer.clone()),
+                optional(trial.correct.map(|correct| correct.to_string())),
+                optional(trial.ms.map(|ms| ms.to_string())),
+            ];
+            csv.write_record(row.iter().cloned().chain(trial))?;
+        }
+    }
+    csv.flush()
+}
+
+fn read_csv<R: Read>(reader: R) -> io::Result<Vec<SessionRecord>> {
+    let mut csv = csv::Reader::from_reader(reader);
+    let header = csv.headers()?.clone();
+    let column = |name: &str| header.iter().position(|column| column == name);
+
+    let mut sessions: Vec<(String, SessionRecord)> = Vec::new();
+    for (line, row) in csv.records().enumerate() {
+        let row = row?;
+        let invalid = |what: &str| {
+            io::Error::new(io::ErrorKind::InvalidData, format!("row {}: {what}", line + 2))
+        };
+        let field = |name: &str| column(name).and_then(|i| row.get(i)).filter(|value| !value.is_empty());
+
+        let id = field("session").ok_or_else(|| invalid("no session"))?.to_string();
+        // Rows of a session follow each other, a new id starts the next session
+        if sessions.last().is_none_or(|(last, _)| *last != id) {
+            let played_at = field("played_at")
+                .and_then(|at| DateTime::parse_from_rfc3339(at).ok())
+                .ok_or_else(|| invalid("played_at is not an RFC 3339 date"))?;
+            let mut session = SessionRecord::new(
+                field("game").ok_or_else(|| invalid("no game"))?,
+                field("mode").unwrap_or_default(),
+                field("score")
+                    .and_then(|score| score.parse().ok())
+                    .ok_or_else(|| invalid("score is not a number"))?,
+            );
+            session.played_at = played_at.with_timezone(&Local);
+            session.aborted = field("aborted") == Some("true");
+            session.imported = field("imported") == Some("true");
+            for (i, name) in header.iter().enumerate() {
+                let value = row.get(i).and_then(|value| value.parse().ok());
+                if let (Some(name), Some(value)) = (name.strip_prefix(METRIC_PREFIX), value) {
+                    session.metrics.insert(name.to_string(), value);
+                }
+            }
+            sessions.push((id, session));
+        }
+
+        if field("trial").is_some() {
+            let trial = Trial {
+                item: field("item").map(str::to_string),
+                answer: field("answer").map(str::to_string),
+                correct: field("correct").and_then(|correct| correct.parse().ok()),
+                ms: field("ms").and_then(|ms| ms.parse().ok()),
+            };
+            if let Some((_, session)) = sessions.last_mut() {
+                session.trials.push(trial);
+            }
+        }
+    }
+
+    Ok(sessions.into_iter().map(|(_, session)| session).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn sessions() -> Vec<SessionRecord> {
+        let mut reaction = SessionRecord::new("reaction", "standard", 231)
+            .with_metric("input_latency_ms", 12.0)
+            .with_metric("false_starts", 1.0)
+            .with_trials(vec![
+                Trial {
+                    correct: Some(true),
+                    ms: Some(224.5),
+                    ..Trial::default()
+                },
+                Trial {
+                    correct: Some(false),
+                    ms: Some(88.0),
+                    ..Trial::default()
+                },
+            ]);
+        reaction.played_at = Local.with_ymd_and_hms(2026, 3, 1, 9, 30, 0).unwrap();
+
+        // No trials and no metrics, given up on
+        let mut verbal = SessionRecord::new("verbal_memory", "classic", 14).aborted();
+        verbal.played_at = Local.with_ymd_and_hms(2026, 3, 2, 21, 5, 0).unwrap();
+
+        let mut number = SessionRecord::new("number_memory", "forward", 7)
+            .with_metric("accuracy", 0.875)
+            .with_trials(vec![Trial {
+                item: Some("0417".to_string()),
+                answer: Some("0471, with a comma".to_string()),
+                correct: Some(false),
+                ms: None,
+            }]);
+        number.played_at = Local.with_ymd_and_hms(2026, 3, 3, 12, 0, 0).unwrap();
+        number.imported = true;
+
+        vec![reaction, verbal, number]
+    }
+
+    fn round_trip(format: Format) {
+        let sessions = sessions();
+        let mut file = Vec::new();
+        write(&sessions, format, &mut file).unwrap();
+        let read = read(format, file.as_slice()).unwrap();
+
+        // Sessions don't compare, their JSON does
+        assert_eq!(serde_json::to_value(&read).unwrap(), serde_json::to_value(&sessions).unwrap());
+    }
+
+    #[test]
+    fn csv_round_trips() {
+        round_trip(Format::Csv);
+    }
+
+    #[test]
+    fn json_round_trips() {
+        round_trip(Format::Json);
+    }
+}
@@ -0,0 +1,96 @@
+/// How a position of the answer compares to the number that was shown.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mark {
+    /// The right symbol in the right place.
+    Correct,
+    /// Swapped with its neighbour.
+    Transposed,
+    /// A different symbol.
+    Wrong,
+    /// The answer stopped before this position.
+    Missing,
+    /// The answer went on past the end of the number.
+    Extra,
+}
+
+/// One position of the comparison, with the symbols on each side.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cell {
+    pub expected: Option<char>,
+    pub given: Option<char>,
+    pub mark: Mark,
+}
+
+/// Compares the answer to the number position by position.
+///
+/// Two neighbouring positions holding each other's symbols are reported as a
+/// transposition rather than two separate mistakes.
+pub fn diff(number: &str, answer: &str) -> Vec<Cell> {
+    let expected: Vec<char> = number.chars().collect();
+    let given: Vec<char> = answer.chars().collect();
+    let len = expected.len().max(given.len());
+
+    let mut cells: Vec<Cell> = (0..len)
+        .map(|i| {
+            let (e, g) = (expected.get(i).copied(), given.get(i).copied());
+            let mark = match (e, g) {
+                (Some(e), Some(g)) if e == g => Mark::Correct,
+                (Some(_), Some(_)) => Mark::Wrong,
+                (Some(_), None) => Mark::Missing,
+                _ => Mark::Extra,
+            };
+            Cell {
+                expected: e,
+                given: g,
+                mark,
+            }
+        })
+        .collect();
+
+    for i in 0..len.saturating_sub(1) {
+        let (a, b) = (cells[i], cells[i + 1]);
+        if a.mark == Mark::Wrong && b.mark == Mark::Wrong && a.expected == b.given && a.given == b.expected {
+            cells[i].mark = Mark::Transposed;
+            cells[i + 1].mark = Mark::Transposed;
+        }
+    }
+
+    cells
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use Mark::*;
+
+    fn marks(number: &str, answer: &str) -> Vec<Mark> {
+        diff(number, answer).iter().map(|cell| cell.mark).collect()
+    }
+
+    #[test]
+    fn equal_numbers_are_all_correct() {
+        assert_eq!(marks("40721", "40721"), vec![Correct; 5]);
+        assert!(diff("", "").is_empty());
+    }
+
+    #[test]
+    fn swapped_neighbours_are_one_transposition() {
+        assert_eq!(marks("40721", "47021"), vec![Correct, Transposed, Transposed, Correct, Correct]);
+        // Not neighbours, so two plain mistakes
+        assert_eq!(marks("40721", "20741"), vec![Wrong, Correct, Correct, Wrong, Correct]);
+    }
+
+    #[test]
+    fn a_dropped_digit_shifts_the_rest() {
+        assert_eq!(marks("40721", "4021"), vec![Correct, Correct, Wrong, Wrong, Missing]);
+        let cells = diff("40721", "4021");
+        assert_eq!(cells[4], Cell { expected: Some('1'), given: None, mark: Missing });
+    }
+
+    #[test]
+    fn an_extra_digit_is_marked_past_the_end() {
+        assert_eq!(marks("407", "4073"), vec![Correct, Correct, Correct, Extra]);
+        let cells = diff("407", "4073");
+        assert_eq!(cells[3], Cell { expected: None, given: Some('3'), mark: Extra });
+    }
+}
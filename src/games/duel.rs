@@ -0,0 +1,425 @@
+use crate::replay;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{Shutdown, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::atomic::{self, AtomicBool};
+use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
+use std::sync::{Arc, OnceLock};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Port a duel is hosted on when the address doesn't say.
+pub const DUEL_PORT: u16 = 7879;
+/// Round trips measured to estimate the clock offset, the fastest one is kept.
+const SYNC_PINGS: u32 = 8;
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(3);
+/// Longest wait for the other side during the handshake.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(5);
+/// How often a host waiting for a player checks whether it was cancelled.
+const ACCEPT_POLL: Duration = Duration::from_millis(50);
+
+/// Start of this process's clock, timestamps on the wire are microseconds since then.
+static EPOCH: OnceLock<Instant> = OnceLock::new();
+
+/// What a player did in a round.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "ms", rename_all = "snake_case")]
+pub enum Press {
+    /// Pressed after green, reaction time in ms measured from the player's own green frame.
+    Reaction(u32),
+    /// Pressed before green, or faster than a human can react.
+    FalseStart,
+    /// Didn't press before the round timed out.
+    Missed,
+}
+
+/// A press and when it landed, in microseconds on the host's clock.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Timed {
+    pub press: Press,
+    pub at_us: u64,
+}
+
+/// Which end of the connection a player is. The host draws the rounds and judges them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    Host,
+    Guest,
+}
+
+/// One line of JSON on the wire.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Message {
+    Hello { name: String },
+    /// Guest's clock when sent, the host answers with a `Pong` right away.
+    Ping { sent_us: u64 },
+    Pong { sent_us: u64, host_us: u64 },
+    /// Clock sync is over, rounds can start.
+    Ready,
+    /// The screen turns green for both at `go_at_us`, on the host's clock.
+    Round { number: u32, go_at_us: u64 },
+    Press { round: u32, press: Timed },
+    /// How a round went and the score after it.
+    Outcome { round: u32, host: Timed, guest: Timed, host_wins: u32, guest_wins: u32 },
+    Bye,
+}
+
+/// What the connection has to tell the game.
+pub enum Event {
+    Connected { opponent: String },
+    Received(Message),
+    Closed(String),
+}
+
+/// What the background thread sends the game.
+enum Incoming {
+    /// Handshake done, with the write half of the stream and the clock offset.
+    Linked { stream: TcpStream, opponent: String, offset_us: i64 },
+    Event(Event),
+}
+
+/// A link to the other player, set up in the background so the game keeps drawing.
+pub struct Connection {
+    role: Role,
+    incoming: Receiver<Incoming>,
+    stream: Option<TcpStream>, // Write half, once connected
+    offset_us: i64,            // Host clock minus ours, 0 on the host
+    cancel: Arc<AtomicBool>,   // Stops a host still waiting for a player
+}
+
+impl Connection {
+    /// Waits for a player to join on `DUEL_PORT`.
+    pub fn host(name: String) -> Self {
+        let cancel = Arc::new(AtomicBool::new(false));
+        let stop = Arc::clone(&cancel);
+        Self::spawn(Role::Host, cancel, name, move || accept(&stop))
+    }
+
+    /// Joins a duel hosted at `host` or `host:port`.
+    pub fn join(address: String, name: String) -> Self {
+        Self::spawn(Role::Guest, Arc::new(AtomicBool::new(false)), name, move || {
+            connect(&address).map(Some)
+        })
+    }
+
+    fn spawn<F>(role: Role, cancel: Arc<AtomicBool>, name: String, open: F) -> Self
+    where
+        F: FnOnce() -> io::Result<Option<TcpStream>> + Send + 'static,
+    {
+        // Both clocks must be running before any timestamp is taken
+        clock_us();
+
+        let (sender, incoming) = mpsc::channel();
+        if replay::is_playing() {
+            // The opponent's side isn't on the tape
+            let _ = sender.send(Incoming::Event(Event::Closed("Duels can't be replayed".to_string())));
+        } else {
+            thread::spawn(move || {
+                let linked = open().and_then(|stream| match stream {
+                    Some(stream) => handshake(role, stream, &name).map(Some),
+                    None => Ok(None),
+                });
+                match linked {
+                    Ok(Some((stream, opponent, offset_us))) => run(stream, opponent, offset_us, &sender),
+                    // Cancelled before anyone joined
+                    Ok(None) => {}
+                    Err(err) => {
+                        let _ = sender.send(Incoming::Event(Event::Closed(format!("Can't connect: {err}"))));
+                    }
+                }
+            });
+        }
+
+        Self {
+            role,
+            incoming,
+            stream: None,
+            offset_us: 0,
+            cancel,
+        }
+    }
+
+    pub fn role(&self) -> Role {
+        self.role
+    }
+
+    /// The next thing that happened on the connection, if any.
+    pub fn poll(&mut self) -> Option<Event> {
+        match self.incoming.try_recv() {
+            Ok(Incoming::Linked { stream, opponent, offset_us }) => {
+                self.stream = Some(stream);
+                self.offset_us = offset_us;
+                Some(Event::Connected { opponent })
+            }
+            Ok(Incoming::Event(Event::Closed(reason))) => {
+                // Said once, the thread ending after it is no news
+                self.stream = None;
+                Some(Event::Closed(reason))
+            }
+            Ok(Incoming::Event(event)) => Some(event),
+            Err(TryRecvError::Empty) => None,
+            // The thread says why before it ends, so it died without a word
+            Err(TryRecvError::Disconnected) => {
+                self.stream.as_ref()?;
+                self.stream = None;
+                Some(Event::Closed("Connection lost".to_string()))
+            }
+        }
+    }
+
+    pub fn send(&mut self, message: &Message) -> io::Result<()> {
+        match self.stream.as_mut() {
+            Some(stream) => write(stream, message),
+            None => Err(io::Error::new(io::ErrorKind::NotConnected, "not connected yet")),
+        }
+    }
+
+    /// The host's clock now, in microseconds.
+    pub fn host_clock_us(&self) -> u64 {
+        (clock_us() as i64 + self.offset_us).max(0) as u64
+    }
+
+    /// When something that happened here at `at` happened on the host's clock, in microseconds.
+    pub fn host_time_us(&self, at: Instant) -> u64 {
+        let local = at.saturating_duration_since(epoch()).as_micros() as i64;
+        (local + self.offset_us).max(0) as u64
+    }
+
+    /// When a time on the host's clock happens here.
+    pub fn local_instant(&self, host_us: u64) -> Instant {
+        let local = (host_us as i64 - self.offset_us).max(0) as u64;
+        epoch() + Duration::from_micros(local)
+    }
+}
+
+impl Drop for Connection {
+    fn drop(&mut self) {
+        self.cancel.store(true, atomic::Ordering::Relaxed);
+        if let Some(stream) = self.stream.as_mut() {
+            // Best effort, the other side also notices the connection closing
+            let _ = write(stream, &Message::Bye);
+            let _ = stream.shutdown(Shutdown::Both);
+        }
+    }
+}
+
+/// Who wins a round, `None` for nobody.
+///
+/// Both screens turn green at the same moment on the host's clock, so the press that lands
+/// first on it wins, whatever reaction time either side reports. A false start gives the round
+/// to the other player, unless they false started too or nobody pressed at all.
+pub fn judge(host: Timed, guest: Timed) -> Option<Role> {
+    match (host.press, guest.press) {
+        (Press::Reaction(_), Press::Reaction(_)) => match host.at_us.cmp(&guest.at_us) {
+            Ordering::Less => Some(Role::Host),
+            Ordering::Greater => Some(Role::Guest),
+            Ordering::Equal => None,
+        },
+        (Press::Reaction(_), _) | (Press::Missed, Press::FalseStart) => Some(Role::Host),
+        (_, Press::Reaction(_)) | (Press::FalseStart, Press::Missed) => Some(Role::Guest),
+        _ => None,
+    }
+}
+
+fn epoch() -> Instant {
+    *EPOCH.get_or_init(Instant::now)
+}
+
+fn clock_us() -> u64 {
+    epoch().elapsed().as_micros() as u64
+}
+
+/// Waits for the first player to connect, `None` if cancelled first.
+fn accept(cancel: &AtomicBool) -> io::Result<Option<TcpStream>> {
+    let listener = TcpListener::bind(("0.0.0.0", DUEL_PORT))?;
+    listener.set_nonblocking(true)?;
+    loop {
+        match listener.accept() {
+            Ok((stream, _)) => {
+                stream.set_nonblocking(false)?;
+                return Ok(Some(stream));
+            }
+            Err(err) if err.kind() == io::ErrorKind::WouldBlock => {
+                if cancel.load(atomic::Ordering::Relaxed) {
+                    return Ok(None);
+                }
+                thread::sleep(ACCEPT_POLL);
+            }
+            Err(err) => return Err(err),
+        }
+    }
+}
+
+/// `host` alone means the default port.
+fn connect(address: &str) -> io::Result<TcpStream> {
+    let address = address.trim();
+    let address = if address.contains(':') {
+        address.to_string()
+    } else {
+        format!("{address}:{DUEL_PORT}")
+    };
+
+    let mut last_error = io::Error::new(io::ErrorKind::NotFound, format!("{address} doesn't resolve"));
+    for address in address.to_socket_addrs()? {
+        match TcpStream::connect_timeout(&address, CONNECT_TIMEOUT) {
+            Ok(stream) => return Ok(stream),
+            Err(err) => last_error = err,
+        }
+    }
+    Err(last_error)
+}
+
+/// Exchanges names and, from the guest, measures the clock offset to the host.
+///
+/// Each ping gives an offset assuming the way there took as long as the way back.
+/// The fastest round trip has the least room for that to be wrong, so it is kept.
+fn handshake(role: Role, stream: TcpStream, name: &str) -> io::Result<(TcpStream, String, i64)> {
+    stream.set_nodelay(true)?;
+    stream.set_read_timeout(Some(HANDSHAKE_TIMEOUT))?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut writer = stream;
+
+    write(&mut writer, &Message::Hello { name: name.to_string() })?;
+    let opponent = match read(&mut reader)? {
+        Message::Hello { name } => name,
+        _ => return Err(unexpected()),
+    };
+
+    let mut offset_us = 0;
+    match role {
+        Role::Host => loop {
+            match read(&mut reader)? {
+                Message::Ping { sent_us } => write(
+                    &mut writer,
+                    &Message::Pong {
+                        sent_us,
+                        host_us: clock_us(),
+                    },
+                )?,
+                Message::Ready => break,
+                _ => return Err(unexpected()),
+            }
+        },
+        Role::Guest => {
+            let mut fastest = u64::MAX;
+            for _ in 0..SYNC_PINGS {
+                write(&mut writer, &Message::Ping { sent_us: clock_us() })?;
+                let Message::Pong { sent_us, host_us } = read(&mut reader)? else {
+                    return Err(unexpected());
+                };
+                let round_trip = clock_us().saturating_sub(sent_us);
+                if round_trip < fastest {
+                    fastest = round_trip;
+                    offset_us = host_us as i64 - (sent_us + round_trip / 2) as i64;
+                }
+            }
+            write(&mut writer, &Message::Ready)?;
+        }
+    }
+
+    writer.set_read_timeout(None)?;
+    Ok((writer, opponent, offset_us))
+}
+
+/// Hands the connection over, then forwards every message until it closes.
+fn run(stream: TcpStream, opponent: String, offset_us: i64, sender: &Sender<Incoming>) {
+    let Ok(writer) = stream.try_clone() else {
+        let _ = sender.send(Incoming::Event(Event::Closed("Can't use the connection".to_string())));
+        return;
+    };
+    let linked = Incoming::Linked {
+        stream: writer,
+        opponent: opponent.clone(),
+        offset_us,
+    };
+    if sender.send(linked).is_err() {
+        return;
+    }
+
+    let mut reader = BufReader::new(stream);
+    loop {
+        let event = match read(&mut reader) {
+            Ok(Message::Bye) => Event::Closed(format!("{opponent} left the duel")),
+            Ok(message) => Event::Received(message),
+            Err(err) => Event::Closed(format!("Connection to {opponent} lost: {err}")),
+        };
+        let closed = matches!(event, Event::Closed(_));
+        // The game is gone, nobody is listening anymore
+        if sender.send(Incoming::Event(event)).is_err() || closed {
+            return;
+        }
+    }
+}
+
+fn read(reader: &mut BufReader<TcpStream>) -> io::Result<Message> {
+    let mut line = String::new();
+    if reader.read_line(&mut line)? == 0 {
+        return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "closed by the other side"));
+    }
+    serde_json::from_str(&line).map_err(io::Error::from)
+}
+
+fn write(stream: &mut TcpStream, message: &Message) -> io::Result<()> {
+    let mut line = serde_json::to_string(message).map_err(io::Error::from)?;
+    line.push('\n');
+    stream.write_all(line.as_bytes())
+}
+
+fn unexpected() -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, "the other side doesn't speak the duel protocol")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn timed(press: Press, at_us: u64) -> Timed {
+        Timed { press, at_us }
+    }
+
+    #[test]
+    fn the_first_press_on_the_host_clock_wins() {
+        // The guest claims a faster reaction, but its press landed later
+        let host = timed(Press::Reaction(240), 1_240_000);
+        let guest = timed(Press::Reaction(150), 1_260_000);
+        assert_eq!(judge(host, guest), Some(Role::Host));
+        assert_eq!(judge(guest, host), Some(Role::Guest));
+        assert_eq!(judge(host, host), None);
+    }
+
+    #[test]
+    fn false_starts_and_misses_lose_to_a_press() {
+        let reaction = timed(Press::Reaction(300), 2_000_000);
+        let false_start = timed(Press::FalseStart, 500_000);
+        let missed = timed(Press::Missed, 4_000_000);
+
+        assert_eq!(judge(false_start, reaction), Some(Role::Guest));
+        assert_eq!(judge(reaction, missed), Some(Role::Host));
+        assert_eq!(judge(missed, false_start), Some(Role::Host));
+        assert_eq!(judge(false_start, missed), Some(Role::Guest));
+        assert_eq!(judge(false_start, false_start), None);
+        assert_eq!(judge(missed, missed), None);
+    }
+
+    #[test]
+    fn handshake_exchanges_names_and_syncs_clocks() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let host = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            handshake(Role::Host, stream, "ann").unwrap()
+        });
+
+        let stream = TcpStream::connect(address).unwrap();
+        let (_stream, opponent, offset_us) = handshake(Role::Guest, stream, "bob").unwrap();
+        let (_host_stream, host_opponent, host_offset_us) = host.join().unwrap();
+
+        assert_eq!(opponent, "ann");
+        assert_eq!(host_opponent, "bob");
+        assert_eq!(host_offset_us, 0);
+        // Both ends share this process's clock, only the round trip's asymmetry is left
+        assert!(offset_us.abs() < 5_000, "offset of {offset_us} µs");
+    }
+}
@@ -0,0 +1,527 @@
+use crate::games::utils::centered_rect;
+use crate::games::Game;
+use crate::keymap::{Action, KeyMap};
+use crate::replay;
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::backend::{Backend, CrosstermBackend};
+use ratatui::layout::{Alignment, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph, Wrap};
+use ratatui::{Frame, Terminal};
+use std::io;
+use std::time::{Duration, Instant};
+
+/// Shortest time between two frames, caps drawing at 60 fps.
+const FRAME_DURATION: Duration = Duration::from_millis(16);
+
+/// Entries of the pause menu, in display order.
+const PAUSE_OPTIONS: [PauseOption; 4] = [
+    PauseOption::Resume,
+    PauseOption::Restart,
+    PauseOption::Help,
+    PauseOption::Quit,
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PauseOption {
+    Resume,
+    Restart,
+    Help,
+    Quit,
+}
+
+/// The pause overlay. While it is up the game gets no updates, so its timers stand still.
+struct PauseMenu {
+    options: Vec<PauseOption>,
+    selected: usize,
+    showing_help: bool,
+}
+
+/// Where the engine's events come from, the terminal unless a replay is playing.
+pub trait EventSource {
+    /// Whether an event is ready within `timeout`, `None` waits for as long as it takes.
+    fn poll(&mut self, timeout: Option<Duration>) -> io::Result<bool>;
+    fn read(&mut self) -> io::Result<Event>;
+
+    /// A line shown above the game, such as how far a replay got.
+    fn status(&self) -> Option<String> {
+        None
+    }
+}
+
+/// Keys typed in the terminal.
+struct TerminalEvents;
+
+impl EventSource for TerminalEvents {
+    fn poll(&mut self, timeout: Option<Duration>) -> io::Result<bool> {
+        match timeout {
+            Some(timeout) => event::poll(timeout),
+            // Reading blocks until there is something
+            None => Ok(true),
+        }
+    }
+
+    fn read(&mut self) -> io::Result<Event> {
+        event::read()
+    }
+}
+
+/// Runs a game: owns the terminal, paces frames and pumps events.
+///
+/// Each turn of the loop dispatches the pending events, updates the game and draws it.
+/// Events are always dispatched before the update that follows them, so a key typed
+/// while a screen was up is handled by that screen and never leaks into the next one.
+///
+/// Shared hotkeys: the Pause action pauses a run in progress, F1 shows the game's help and
+/// Ctrl+C goes back to the menu. F1 and Ctrl+C can't be rebound. Auto-repeat never reaches a game.
+pub struct Engine<'a, B: Backend> {
+    terminal: &'a mut Terminal<B>,
+    keys: &'a KeyMap,
+    events: Box<dyn EventSource + 'a>,
+    pause: Option<PauseMenu>,
+    // When the game was last updated, moved along when it must not see the time that passed
+    last_update: Instant,
+    leave: bool,
+}
+
+impl<'a> Engine<'a, CrosstermBackend<io::Stdout>> {
+    pub fn new(terminal: &'a mut Terminal<CrosstermBackend<io::Stdout>>, keys: &'a KeyMap) -> Self {
+        Self::with_events(terminal, keys, Box::new(TerminalEvents))
+    }
+}
+
+impl<'a, B: Backend> Engine<'a, B> {
+    /// Runs on events from elsewhere than the terminal, a replay for instance.
+    ///
+    /// Time is read from `replay::now`, so a source that plays back events moves the clock along with them.
+    pub fn with_events(
+        terminal: &'a mut Terminal<B>,
+        keys: &'a KeyMap,
+        events: Box<dyn EventSource + 'a>,
+    ) -> Self {
+        Self {
+            terminal,
+            keys,
+            events,
+            pause: None,
+            last_update: replay::now(),
+            leave: false,
+        }
+    }
+
+    pub fn run(&mut self, game: &mut dyn Game) -> io::Result<()> {
+        self.leave = false;
+        self.pause = None;
+        self.last_update = replay::now();
+        let mut last_frame = replay::now();
+        game.set_keys(self.keys);
+
+        loop {
+            let now = replay::now();
+            // Time spent paused is skipped, not caught up on
+            if self.pause.is_none() {
+                game.update(now.duration_since(self.last_update));
+            }
+            self.last_update = now;
+
+            if self.leave || game.wants_exit() {
+                break;
+            }
+
+            let wake_in = if self.pause.is_some() { None } else { game.wake_in() };
+
+            // Don't draw faster than the frame rate, however often events come in
+            let since_frame = replay::now().duration_since(last_frame);
+            if since_frame < FRAME_DURATION && wake_in.is_some() {
+                self.pump_events(game, Some(FRAME_DURATION - since_frame))?;
+                continue;
+            }
+
+            let draw_start = replay::now();
+            let (pause, keys, status) = (&self.pause, self.keys, self.events.status());
+            self.terminal.draw(|frame| {
+                match pause {
+                    // The game is hidden while paused, pausing must not buy extra time to memorize
+                    Some(pause) => pause.render(frame, game, keys),
+                    None => game.render(frame),
+                }
+                if let Some(status) = status {
+                    render_status(frame, status);
+                }
+            })?;
+            last_frame = replay::now();
+            if self.pause.is_none() {
+                game.on_frame_drawn(last_frame, last_frame.duration_since(draw_start));
+            }
+
+            let timeout = wake_in.map(|wait| wait.max(FRAME_DURATION));
+            self.pump_events(game, timeout)?;
+            if timeout.is_none() {
+                // Nothing was ticking while waiting for a key, a timer it started counts from here
+                self.last_update = replay::now();
+            }
+        }
+
+        game.on_exit();
+        Ok(())
+    }
+
+    /// Waits up to `timeout` (forever with `None`) for an event, then dispatches it along
+    /// with every other event already queued.
+    fn pump_events(&mut self, game: &mut dyn Game, timeout: Option<Duration>) -> io::Result<()> {
+        if !self.events.poll(timeout)? {
+            return Ok(());
+        }
+
+        let event = self.events.read()?;
+        self.dispatch(game, event)?;
+        while !self.leave && self.events.poll(Some(Duration::ZERO))? {
+            let event = self.events.read()?;
+            self.dispatch(game, event)?;
+        }
+        Ok(())
+    }
+
+    /// Handles the hotkeys shared by every game, the rest goes to the game.
+    fn dispatch(&mut self, game: &mut dyn Game, event: Event) -> io::Result<()> {
+        replay::record(&event);
+        match event {
+            Event::Key(key)
+                if key.kind == KeyEventKind::Press
+                    && key.modifiers.contains(KeyModifiers::CONTROL)
+                    && key.code == KeyCode::Char('c') =>
+            {
+                // Ctrl+C always gets back to the menu
+                self.leave = true;
+            }
+            // A held key is pressed once, whatever the terminal repeats. Terminals that report
+            // every key also send Shift or Ctrl alone, which doesn't count as a press either
+            Event::Key(key) if key.kind == KeyEventKind::Repeat || matches!(key.code, KeyCode::Modifier(_)) => {}
+            Event::Resize(_, _) => {
+                self.terminal.autoresize()?;
+            }
+            Event::Key(key) if self.pause.is_some() => {
+                if key.kind == KeyEventKind::Press {
+                    self.handle_pause_key(game, key);
+                }
+            }
+            Event::Key(key) if key.kind == KeyEventKind::Press && key.code == KeyCode::F(1) => {
+                self.pause = Some(PauseMenu::new(game, true));
+                game.on_pause();
+            }
+            Event::Key(key)
+                if key.kind == KeyEventKind::Press && self.keys.is(Action::Pause, &key) && game.is_running() =>
+            {
+                self.pause = Some(PauseMenu::new(game, false));
+                game.on_pause();
+            }
+            event => game.on_event(event, self.keys),
+        }
+        Ok(())
+    }
+
+    fn handle_pause_key(&mut self, game: &mut dyn Game, key: KeyEvent) {
+        let Some(pause) = self.pause.as_mut() else {
+            return;
+        };
+
+        if pause.showing_help {
+            // Help opened with F1 outside of a run has no menu to go back to
+            pause.showing_help = false;
+            if !game.is_running() {
+                self.close_pause();
+            }
+            return;
+        }
+
+        let action = self.keys.action(&key, &[Action::Up, Action::Down, Action::Confirm, Action::Pause, Action::Back]);
+        let chosen = match action {
+            Some(Action::Up) => {
+                pause.selected = pause.selected.saturating_sub(1);
+                None
+            }
+            Some(Action::Down) => {
+                pause.selected = (pause.selected + 1).min(pause.options.len() - 1);
+                None
+            }
+            Some(Action::Confirm) => Some(pause.options[pause.selected]),
+            Some(_) => Some(PauseOption::Resume),
+            None => None,
+        };
+
+        match chosen {
+            Some(PauseOption::Resume) => {
+                self.close_pause();
+                game.on_resume();
+            }
+            Some(PauseOption::Restart) => {
+                self.close_pause();
+                game.restart();
+            }
+            Some(PauseOption::Help) => pause.showing_help = true,
+            Some(PauseOption::Quit) => self.leave = true,
+            None => {}
+        }
+    }
+
+    // The game's timers pick up where they were, the time spent paused never reaches `update`
+    fn close_pause(&mut self) {
+        self.pause = None;
+        self.last_update = replay::now();
+    }
+}
+
+/// The event source's line over the top row of the screen.
+fn render_status(frame: &mut Frame, status: String) {
+    let area = Rect {
+        height: 1.min(frame.area().height),
+        ..frame.area()
+    };
+    let line = Paragraph::new(status)
+        .alignment(Alignment::Center)
+        .style(Style::default().fg(Color::Black).bg(Color::Yellow));
+    frame.render_widget(Clear, area);
+    frame.render_widget(line, area);
+}
+
+impl PauseOption {
+    fn label(&self) -> &'static str {
+        match self {
+            PauseOption::Resume => "Resume",
+            PauseOption::Restart => "Restart",
+            PauseOption::Help => "Help",
+            PauseOption::Quit => "Quit to menu",
+        }
+    }
+}
+
+impl PauseMenu {
+    fn new(game: &dyn Game, showing_help: bool) -> Self {
+        let options = PAUSE_OPTIONS
+            .into_iter()
+            .filter(|option| *option != PauseOption::Restart || game.can_restart())
+            .collect();
+        Self {
+            options,
+            selected: 0,
+            showing_help,
+        }
+    }
+
+    fn render(&self, frame: &mut Frame, game: &dyn Game, keys: &KeyMap) {
+        let style = Style::default().fg(Color::White).bg(Color::Black);
+
+        if self.showing_help {
+            let mut lines: Vec<Line> = game.help().lines().map(Line::from).collect();
+            lines.push(Line::from(""));
+            lines.push(Line::from(format!(
+                "Keys: up {} • down {} • left {} • right {} • confirm {} • back {}",
+                keys.label(Action::Up),
+                keys.label(Action::Down),
+                keys.label(Action::Left),
+                keys.label(Action::Right),
+                keys.label(Action::Confirm),
+                keys.label(Action::Back),
+            )));
+            lines.push(Line::from(format!(
+                "{} pauses a run • F1 shows this help • Ctrl+C back to the menu",
+                keys.label(Action::Pause)
+            )));
+            lines.push(Line::from("Press any key to go back"));
+
+            // Leave room for the lines the box is too narrow for
+            let height: usize = lines.iter().map(|line| line.width().max(1).div_ceil(68)).sum();
+            let area = centered_rect(frame.area(), 70, height as u16 + 2);
+            let help = Paragraph::new(lines)
+                .style(style)
+                .wrap(Wrap { trim: false })
+                .block(Block::default().title(format!(" {} • Help ", game.name())).borders(Borders::ALL));
+
+            frame.render_widget(Clear, area);
+            frame.render_widget(help, area);
+            return;
+        }
+
+        let items: Vec<ListItem> = self
+            .options
+            .iter()
+            .map(|option| ListItem::new(Line::from(option.label()).alignment(Alignment::Center)))
+            .collect();
+
+        let area = centered_rect(frame.area(), 30, self.options.len() as u16 + 2);
+        let list = List::new(items)
+            .style(style)
+            .block(Block::default().title(" Paused ").title_alignment(Alignment::Center).borders(Borders::ALL))
+            .highlight_style(
+                Style::default()
+                    .bg(Color::Blue)
+                    .fg(Color::White)
+                    .add_modifier(Modifier::BOLD),
+            );
+
+        let mut state = ListState::default();
+        state.select(Some(self.selected));
+
+        frame.render_widget(Clear, area);
+        frame.render_stateful_widget(list, area, &mut state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::games::number_memory::NumberMemory;
+    use crate::games::ReactionGame;
+    use crate::storage;
+    use ratatui::backend::TestBackend;
+    use std::collections::VecDeque;
+    use std::thread;
+
+    /// Events played at set delays after the previous one.
+    struct Script {
+        events: VecDeque<(Duration, Event)>,
+        since: Instant,
+    }
+
+    impl EventSource for Script {
+        fn poll(&mut self, timeout: Option<Duration>) -> io::Result<bool> {
+            let Some((delay, _)) = self.events.front() else {
+                thread::sleep(timeout.unwrap_or_default());
+                return Ok(false);
+            };
+            let wait = (self.since + *delay).saturating_duration_since(Instant::now());
+            match timeout {
+                Some(timeout) if timeout < wait => {
+                    thread::sleep(timeout);
+                    Ok(false)
+                }
+                _ => {
+                    thread::sleep(wait);
+                    Ok(true)
+                }
+            }
+        }
+
+        fn read(&mut self) -> io::Result<Event> {
+            self.since = Instant::now();
+            Ok(self.events.pop_front().expect("read after a successful poll").1)
+        }
+    }
+
+    /// Passes everything to the game, noting how long it had left to wait when paused and once resumed.
+    struct Probe<G: Game> {
+        game: G,
+        paused_with: Option<Duration>,
+        resumed_with: Option<Duration>,
+        resumed: bool,
+    }
+
+    impl<G: Game> Game for Probe<G> {
+        fn name(&self) -> &str {
+            self.game.name()
+        }
+        fn description(&self) -> &str {
+            self.game.description()
+        }
+        fn id(&self) -> &str {
+            self.game.id()
+        }
+        fn help(&self) -> &str {
+            self.game.help()
+        }
+        fn update(&mut self, dt: Duration) {
+            self.game.update(dt);
+            if self.resumed && self.resumed_with.is_none() {
+                self.resumed_with = self.game.wake_in();
+            }
+        }
+        fn render(&self, frame: &mut Frame) {
+            self.game.render(frame)
+        }
+        fn on_event(&mut self, event: Event, keys: &KeyMap) {
+            self.game.on_event(event, keys)
+        }
+        fn handle_input(&mut self, key_event: KeyEvent, keys: &KeyMap) {
+            self.game.handle_input(key_event, keys)
+        }
+        fn wants_exit(&self) -> bool {
+            self.game.wants_exit()
+        }
+        fn wake_in(&self) -> Option<Duration> {
+            self.game.wake_in()
+        }
+        fn on_frame_drawn(&mut self, at: Instant, draw_time: Duration) {
+            self.game.on_frame_drawn(at, draw_time)
+        }
+        fn is_running(&self) -> bool {
+            self.game.is_running()
+        }
+        fn on_pause(&mut self) {
+            self.paused_with = self.game.wake_in();
+            self.game.on_pause()
+        }
+        fn on_resume(&mut self) {
+            self.resumed = true;
+            self.game.on_resume()
+        }
+        fn restart(&mut self) {
+            self.game.restart()
+        }
+        fn on_exit(&mut self) {
+            self.game.on_exit()
+        }
+    }
+
+    fn key(code: KeyCode) -> Event {
+        Event::Key(KeyEvent::new(code, KeyModifiers::NONE))
+    }
+
+    /// Starts a run, pauses it for a while, resumes and returns the time left before and after.
+    fn pause_and_resume<G: Game>(game: G) -> (Duration, Duration) {
+        storage::use_test_dir();
+        let pause = Duration::from_millis(400);
+        let events = [
+            (Duration::ZERO, key(KeyCode::Enter)),
+            (Duration::from_millis(100), key(KeyCode::Esc)),
+            (pause, key(KeyCode::Enter)),
+            (Duration::ZERO, Event::Key(KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL))),
+        ];
+        let script = Script {
+            events: events.into(),
+            since: Instant::now(),
+        };
+
+        let mut terminal = Terminal::new(TestBackend::new(100, 40)).unwrap();
+        let keys = KeyMap::default();
+        let mut probe = Probe {
+            game,
+            paused_with: None,
+            resumed_with: None,
+            resumed: false,
+        };
+        Engine::with_events(&mut terminal, &keys, Box::new(script)).run(&mut probe).unwrap();
+
+        let paused_with = probe.paused_with.expect("the run was paused while its timer ran");
+        let resumed_with = probe.resumed_with.expect("the run was updated after resuming");
+        (paused_with, resumed_with)
+    }
+
+    #[test]
+    fn resuming_keeps_reaction_wait() {
+        let (paused_with, resumed_with) = pause_and_resume(ReactionGame::new());
+        assert!(
+            paused_with.abs_diff(resumed_with) < Duration::from_millis(100),
+            "{paused_with:?} then {resumed_with:?}"
+        );
+    }
+
+    #[test]
+    fn resuming_keeps_number_memory_display() {
+        let (paused_with, resumed_with) = pause_and_resume(NumberMemory::new());
+        assert!(
+            paused_with.abs_diff(resumed_with) < Duration::from_millis(100),
+            "{paused_with:?} then {resumed_with:?}"
+        );
+    }
+}
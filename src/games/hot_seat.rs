@@ -0,0 +1,228 @@
+use crate::games::utils::line_with_color;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::Line;
+
+/// Players taking turns on one keyboard.
+pub const MIN_PLAYERS: usize = 2;
+pub const MAX_PLAYERS: usize = 4;
+const MAX_NAME_LENGTH: usize = 16;
+
+/// One of the players of a pass-and-play game.
+pub struct Player {
+    pub name: String,
+    /// Points so far, what they count depends on the game.
+    pub score: u32,
+    /// Lives left, `None` when mistakes don't cost any.
+    pub lives: Option<u32>,
+    /// Knocked out, the turn skips them.
+    pub out: bool,
+}
+
+/// Pass-and-play: whose turn it is and how everyone is doing.
+///
+/// Games keep their own state machine for a turn and ask this who plays next.
+pub struct HotSeat {
+    pub players: Vec<Player>,
+    current: usize,
+    /// Full turns of the table, from 1.
+    pub round: u32,
+    /// How the turn that just ended went, shown when passing to the next player.
+    pub last_turn: Option<String>,
+}
+
+impl HotSeat {
+    pub fn new(names: Vec<String>, lives: Option<u32>) -> Self {
+        let players = names
+            .into_iter()
+            .map(|name| Player {
+                name,
+                score: 0,
+                lives,
+                out: false,
+            })
+            .collect();
+
+        Self {
+            players,
+            current: 0,
+            round: 1,
+            last_turn: None,
+        }
+    }
+
+    pub fn current(&self) -> &Player {
+        &self.players[self.current]
+    }
+
+    pub fn current_mut(&mut self) -> &mut Player {
+        &mut self.players[self.current]
+    }
+
+    /// Hands the turn to the next player still in, `false` once everyone is out.
+    pub fn advance(&mut self) -> bool {
+        let count = self.players.len();
+        for step in 1..=count {
+            let next = (self.current + step) % count;
+            if !self.players[next].out {
+                // Going back to the start of the table begins a new round
+                if next <= self.current {
+                    self.round += 1;
+                }
+                self.current = next;
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Players from best to worst with their place, ties share it.
+    pub fn standings(&self) -> Vec<(usize, &Player)> {
+        let mut players: Vec<&Player> = self.players.iter().collect();
+        players.sort_by_key(|player| std::cmp::Reverse(player.score));
+
+        let mut standings: Vec<(usize, &Player)> = Vec::new();
+        for (index, player) in players.into_iter().enumerate() {
+            let place = match standings.last() {
+                Some((place, previous)) if previous.score == player.score => *place,
+                _ => index + 1,
+            };
+            standings.push((place, player));
+        }
+        standings
+    }
+
+    /// Screen shown between turns, so the keyboard changes hands before anything is revealed.
+    pub fn turn_lines(&self, detail: &str, color: Color) -> Vec<Line<'static>> {
+        let mut lines = Vec::new();
+        if let Some(last) = &self.last_turn {
+            lines.push(line_with_color(last.clone(), color));
+            lines.push(Line::from(""));
+        }
+        lines.push(line_with_color(format!("Round {}", self.round), color));
+        lines.push(
+            line_with_color(format!("{}'s turn", self.current().name), color)
+                .style(Style::default().add_modifier(Modifier::BOLD)),
+        );
+        lines.push(line_with_color(detail.to_string(), color));
+        lines.push(Line::from(""));
+        for player in &self.players {
+            let status = match (player.out, player.lives) {
+                (true, _) => "out".to_string(),
+                (false, Some(lives)) => format!("{lives} lives"),
+                (false, None) => "in".to_string(),
+            };
+            lines.push(line_with_color(format!("{}: {} • {status}", player.name, player.score), color));
+        }
+        lines.push(Line::from(""));
+        lines.push(line_with_color("Press to start the turn", color));
+        lines
+    }
+
+    /// Final ranking, in place of the single player end screen.
+    pub fn standings_lines(&self, unit: &str, color: Color) -> Vec<Line<'static>> {
+        let mut lines = vec![
+            line_with_color("Final standings", color).style(Style::default().add_modifier(Modifier::BOLD)),
+            line_with_color(format!("after {} rounds", self.round), color),
+            Line::from(""),
+        ];
+        for (place, player) in self.standings() {
+            let medal = match place {
+                1 => "🥇",
+                2 => "🥈",
+                3 => "🥉",
+                _ => "  ",
+            };
+            lines.push(line_with_color(format!("{medal} {place}. {}  {} {unit}", player.name, player.score), color));
+        }
+        lines.push(Line::from(""));
+        lines.push(line_with_color("Press to continue", color));
+        lines
+    }
+}
+
+/// Typing the names of the players before a pass-and-play game.
+pub struct NameEntry {
+    names: Vec<String>,
+    typing: String,
+    message: Option<String>,
+}
+
+impl NameEntry {
+    /// `first` is filled in already, typically the player whose profile is active.
+    pub fn new(first: Option<String>) -> Self {
+        Self {
+            names: first.into_iter().collect(),
+            typing: String::new(),
+            message: None,
+        }
+    }
+
+    /// Enter adds the typed name, or starts once enough players are in. Esc cancels.
+    ///
+    /// Returns `Some(true)` to start, `Some(false)` to cancel.
+    pub fn handle_key(&mut self, key_event: KeyEvent) -> Option<bool> {
+        self.message = None;
+        match key_event.code {
+            KeyCode::Enter if self.typing.trim().is_empty() => {
+                if self.names.len() >= MIN_PLAYERS {
+                    return Some(true);
+                }
+                self.message = Some(format!("At least {MIN_PLAYERS} players are needed"));
+            }
+            KeyCode::Enter => {
+                let name = self.typing.trim().to_string();
+                if self.names.iter().any(|other| other.eq_ignore_ascii_case(&name)) {
+                    self.message = Some(format!("{name} is already playing"));
+                } else {
+                    self.names.push(name);
+                    self.typing.clear();
+                }
+                if self.names.len() == MAX_PLAYERS {
+                    return Some(true);
+                }
+            }
+            KeyCode::Esc => return Some(false),
+            // Erasing past an empty name takes back the last player
+            KeyCode::Backspace if self.typing.is_empty() => {
+                self.names.pop();
+            }
+            KeyCode::Backspace => {
+                self.typing.pop();
+            }
+            KeyCode::Char(c)
+                if !key_event.modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT)
+                    && self.typing.chars().count() < MAX_NAME_LENGTH =>
+            {
+                self.typing.push(c);
+            }
+            _ => {}
+        }
+        None
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        self.names.clone()
+    }
+
+    pub fn lines(&self, color: Color) -> Vec<Line<'static>> {
+        let mut lines = vec![
+            line_with_color("Pass and play", color).style(Style::default().add_modifier(Modifier::BOLD)),
+            line_with_color(format!("{MIN_PLAYERS} to {MAX_PLAYERS} players take turns on this keyboard"), color),
+            Line::from(""),
+        ];
+        for (index, name) in self.names.iter().enumerate() {
+            lines.push(line_with_color(format!("Player {}: {name}", index + 1), color));
+        }
+        lines.push(line_with_color(format!("Player {}: {}▏", self.names.len() + 1, self.typing), color));
+        lines.push(Line::from(""));
+        if let Some(message) = &self.message {
+            lines.push(line_with_color(message.clone(), Color::Red));
+        }
+        lines.push(line_with_color(
+            "Type a name and Enter to add it • Enter on an empty name to start • Esc to cancel",
+            color,
+        ));
+        lines
+    }
+}
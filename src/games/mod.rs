@@ -1,18 +1,27 @@
+mod digit_diff;
+mod duel;
+mod engine;
+mod hot_seat;
 mod number_memory;
 mod reaction;
+pub mod settings;
 mod r#trait;
 mod utils;
 mod verbal_memory;
+mod word_list;
+mod word_selection;
 
 use crate::games::number_memory::NumberMemory;
 use crate::games::verbal_memory::VerbalMemory;
+pub use engine::{Engine, EventSource};
 pub use reaction::ReactionGame;
-pub use r#trait::Game;
+pub use r#trait::{Category, Game};
+use crate::cli::Cli;
 
-pub fn get_all_games() -> Vec<Box<dyn Game>> {
+pub fn get_all_games(cli: &Cli) -> Vec<Box<dyn Game>> {
     vec![
         Box::new(ReactionGame::new()),
-        Box::new(VerbalMemory::new()),
+        Box::new(VerbalMemory::new(cli.word_list.clone())),
         Box::new(NumberMemory::new()),
     ]
 }
@@ -1,18 +1,39 @@
-use crate::games::Game;
-use crate::games::utils::line_with_color;
-use crossterm::event::{KeyCode, KeyEvent};
+use crate::daily::{self, DailyStatus};
+use crate::games::{Category, Game};
+use crate::games::digit_diff::{self, Mark};
+use crate::games::hot_seat::{HotSeat, NameEntry};
+use crate::games::settings::{self, Setting};
+use crate::games::utils::{line_with_color, render_distribution, ConfirmDialog};
+use crate::history::{self, Ranking, SessionRecord, Trial};
+use crate::keymap::{Action, KeyMap};
+use crate::profile;
+use crate::reference::{self, Distribution};
+use crate::replay::{self, GameRng};
+use crate::storage;
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyEventKind};
 use rand::Rng;
-use ratatui::backend::CrosstermBackend;
-use ratatui::layout::{Alignment, Constraint, Direction, Layout};
+use ratatui::layout::{Alignment, Constraint, Direction, Layout, Rect};
 use ratatui::prelude::{Color, Modifier, Style};
 use ratatui::text::{Line, Span};
 use ratatui::widgets::{Block, Borders, Gauge, Paragraph};
-use ratatui::{Frame, Terminal};
+use ratatui::Frame;
+use serde::{Deserialize, Serialize};
 use std::cmp::PartialEq;
-use std::io;
-use std::io::Stdout;
 use std::time::{Duration, Instant};
 
+const GAME_ID: &str = "number_memory";
+const CONFIG_FILE: &str = "number_memory.json";
+/// Chunk sizes the number can be shown in, `None` shows it in one piece.
+const GROUPINGS: [Option<usize>; 3] = [None, Some(3), Some(4)];
+/// Shortest time a number can be shown for, whatever its length.
+const MIN_BASE_MS: u64 = 100;
+/// Most extra display time a symbol can add.
+const MAX_PER_SYMBOL_MS: u64 = 5_000;
+/// Symbols typed closer together than this come from a paste or a macro, not from fingers.
+const MIN_TYPING_GAP: Duration = Duration::from_millis(15);
+/// Gaps that short in a row before the answer is thrown out, one alone can be two keys rolled over.
+const BURST_GAPS: u32 = 3;
+
 /// Represents the different states the game can be in during its execution.
 #[derive(Debug, PartialEq, Eq)]
 enum GameState {
@@ -20,12 +41,108 @@ enum GameState {
     Title,
     /// The state in which the number to be remembered is shown to the player.
     Showing,
+    /// The state in which the player checks an equation before recalling the number (operation span).
+    Operation,
     /// The state in which the game is waiting for the player's input.
     Waiting,
     /// The state entered when the player's input matches the shown number.
     Success,
+    /// The state entered after a wrong answer in practice mode, showing what went wrong.
+    Review,
     /// The state entered when the game ends due to failure.
     End,
+    /// The state in which the names of a pass-and-play game are typed.
+    Players,
+    /// The state between two turns of a pass-and-play game, announcing who plays next.
+    Turn,
+    /// The state entered when every player of a pass-and-play game is out.
+    Standings,
+}
+
+/// The symbols the numbers to remember are made of.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+enum Alphabet {
+    Decimal,
+    Hex,
+    Binary,
+    Letters,
+    Alphanumeric,
+}
+
+/// What the player has to do with the number once it's gone.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+enum Variant {
+    /// Type the number as shown.
+    Forward,
+    /// Type the number in reverse order.
+    Backward,
+    /// Check an equation first, then type the number as shown.
+    Operation,
+}
+
+fn grouping_name(size: Option<usize>) -> String {
+    match size {
+        Some(size) => format!("Chunks of {size}"),
+        None => "None".to_string(),
+    }
+}
+
+/// An equation shown between the number and its recall in operation span.
+struct Operation {
+    text: String,
+    is_true: bool,
+}
+
+/// Number memory preferences, kept in the data directory between runs.
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+struct NumberMemoryConfig {
+    alphabet: Alphabet,
+    variant: Variant,
+    /// Size of the chunks the number is split in when shown, `None` to show it in one piece.
+    group_size: Option<usize>,
+    /// Whether a wrong answer shows what went wrong and keeps going instead of ending the run.
+    practice: bool,
+    display_time: DisplayTime,
+}
+
+impl Default for NumberMemoryConfig {
+    fn default() -> Self {
+        Self {
+            alphabet: Alphabet::Decimal,
+            variant: Variant::Forward,
+            group_size: None,
+            practice: false,
+            display_time: DisplayTime::default(),
+        }
+    }
+}
+
+/// How long a number stays on screen: `base_ms + per_symbol_ms × level`, capped at `max_ms`.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+struct DisplayTime {
+    base_ms: u64,
+    per_symbol_ms: u64,
+    max_ms: u64,
+}
+
+impl Default for DisplayTime {
+    fn default() -> Self {
+        // 1.7 s for a single digit, like it used to be for every level
+        Self {
+            base_ms: 1100,
+            per_symbol_ms: 600,
+            max_ms: 15_000,
+        }
+    }
+}
+
+impl DisplayTime {
+    fn for_level(&self, level: u32) -> Duration {
+        let millis = self.per_symbol_ms.saturating_mul(level as u64).saturating_add(self.base_ms);
+        Duration::from_millis(millis.min(self.max_ms))
+    }
 }
 
 /// Represents a single session of the number memory game.
@@ -39,14 +156,154 @@ pub struct NumberMemory {
     number: Option<String>,
     /// The player's input in response to the shown number.
     answer: Option<String>,
+    /// When the last symbol of the answer was typed.
+    last_typed: Option<Instant>,
+    /// Symbols in a row typed faster than `MIN_TYPING_GAP`.
+    quick_gaps: u32,
+    /// The answer was pasted or typed faster than humanly possible, and thrown out.
+    rejected: bool,
     /// The current level of difficulty (increases as the player succeeds).
     level: u32,
     /// Indicates whether the player has chosen to quit the game.
     quit: bool,
-    /// The timestamp marking when the number started being shown.
-    show_start: Option<Instant>,
-    /// The duration for which the number is shown before disappearing.
+    /// How long the number has been shown so far.
+    shown_for: Duration,
+    /// The duration for which the number is shown before disappearing, grows with the level.
     showing_duration: Duration,
+    /// How the showing duration is derived from the level.
+    display_time: DisplayTime,
+    /// Whether wrong answers are reviewed instead of ending the run.
+    practice: bool,
+    /// Wrong answers given so far in a practice run.
+    practice_misses: u32,
+    /// The symbols numbers are generated from and answers are checked against.
+    alphabet: Alphabet,
+    /// How the number has to be recalled.
+    variant: Variant,
+    /// The equation to check, during operation span.
+    operation: Option<Operation>,
+    /// Equations checked and how many of them were right, during operation span.
+    operations_checked: u32,
+    operations_correct: u32,
+    /// Size of the chunks numbers are displayed in, if any.
+    group_size: Option<usize>,
+    /// Asks before a run in progress is thrown away.
+    confirm: Option<ConfirmDialog>,
+    /// Keys named by the hints.
+    keys: KeyMap,
+    /// How other people score in the mode of the run that just ended.
+    reference: Option<Distribution>,
+    /// Every number of the current run with the answer given.
+    trials: Vec<Trial>,
+    /// Names typed for a pass-and-play game, before it starts.
+    names: Option<NameEntry>,
+    /// Players of a pass-and-play game, their score is the longest number they remembered.
+    hot_seat: Option<HotSeat>,
+    /// Draws the numbers and equations, seeded from the date for the daily challenge.
+    rng: GameRng,
+    /// Whether the run is today's challenge.
+    daily: bool,
+    /// Today's challenge, streak and best days.
+    daily_status: DailyStatus,
+}
+
+impl Variant {
+    const ALL: [Variant; 3] = [Variant::Forward, Variant::Backward, Variant::Operation];
+
+    fn name(&self) -> &'static str {
+        match self {
+            Variant::Forward => "Forward span",
+            Variant::Backward => "Backward span",
+            Variant::Operation => "Operation span",
+        }
+    }
+
+    fn prompt(&self) -> &'static str {
+        match self {
+            Variant::Backward => "What was the number, backwards?",
+            Variant::Forward | Variant::Operation => "What was the number?",
+        }
+    }
+}
+
+impl Operation {
+    /// A one digit addition or subtraction, shown with the right result half of the time.
+    fn random(rng: &mut impl Rng) -> Self {
+        let a: i32 = rng.random_range(1..10);
+        let b: i32 = rng.random_range(1..10);
+        let (symbol, result) = if rng.random_bool(0.5) { ('+', a + b) } else { ('-', a - b) };
+
+        let is_true = rng.random_bool(0.5);
+        let shown = if is_true {
+            result
+        } else {
+            let off = rng.random_range(1..4);
+            if rng.random_bool(0.5) { result + off } else { result - off }
+        };
+
+        Self {
+            text: format!("{a} {symbol} {b} = {shown}"),
+            is_true,
+        }
+    }
+}
+
+impl Alphabet {
+    const ALL: [Alphabet; 5] = [
+        Alphabet::Decimal,
+        Alphabet::Hex,
+        Alphabet::Binary,
+        Alphabet::Letters,
+        Alphabet::Alphanumeric,
+    ];
+
+    fn name(&self) -> &'static str {
+        match self {
+            Alphabet::Decimal => "Decimal",
+            Alphabet::Hex => "Hexadecimal",
+            Alphabet::Binary => "Binary",
+            Alphabet::Letters => "Letters",
+            Alphabet::Alphanumeric => "Alphanumeric",
+        }
+    }
+
+    /// Stored with the results, so it must not change.
+    fn id(&self) -> &'static str {
+        match self {
+            Alphabet::Decimal => "decimal",
+            Alphabet::Hex => "hex",
+            Alphabet::Binary => "binary",
+            Alphabet::Letters => "letters",
+            Alphabet::Alphanumeric => "alphanumeric",
+        }
+    }
+
+    fn symbols(&self) -> &'static [u8] {
+        match self {
+            Alphabet::Decimal => b"0123456789",
+            Alphabet::Hex => b"0123456789ABCDEF",
+            Alphabet::Binary => b"01",
+            Alphabet::Letters => b"ABCDEFGHIJKLMNOPQRSTUVWXYZ",
+            Alphabet::Alphanumeric => b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ",
+        }
+    }
+
+    /// Whether a leading `0` should be avoided, as it would read like padding.
+    fn is_numeric(&self) -> bool {
+        matches!(self, Alphabet::Decimal | Alphabet::Hex | Alphabet::Binary)
+    }
+
+    /// The symbol a typed character stands for, if it belongs to the alphabet.
+    ///
+    /// Only ASCII is accepted, other scripts' digits look right but can never match.
+    /// Letters are case-insensitive.
+    fn symbol_for(&self, c: char) -> Option<char> {
+        if !c.is_ascii() {
+            return None;
+        }
+        let upper = c.to_ascii_uppercase();
+        self.symbols().contains(&(upper as u8)).then_some(upper)
+    }
 }
 
 
@@ -59,115 +316,420 @@ impl Game for NumberMemory {
         "Remember the longest number you can"
     }
 
-    fn handle_input(&mut self, key_event: KeyEvent) {
+    fn id(&self) -> &str {
+        GAME_ID
+    }
+
+    fn category(&self) -> Category {
+        Category::Memory
+    }
+
+    fn score_unit(&self) -> &str {
+        "symbols"
+    }
+
+    fn help(&self) -> &str {
+        "A number is shown for a few seconds, type it back once it disappears.\n\
+         Each right answer adds a symbol, the first mistake ends the run.\n\
+         Backward span: type the number in reverse order.\n\
+         Operation span: judge a small sum between the number and your answer.\n\
+         \n\
+         Title screen: up/down symbols, left/right grouping, v variant,\n\
+         Tab practice mode (misses show a comparison instead of ending the run),\n\
+         p to pass and play with 2 to 4 players, t for today's challenge\n\
+         (the same numbers for everyone in decimal forward span, one attempt a day),\n\
+         any other key to start\n\
+         Pass and play: each turn is one number at the player's own level,\n\
+         a mistake knocks them out, the last one standing has the longest number\n\
+         Operation: y or left if it is true, n or right if it is false\n\
+         Answer: type the symbols, Backspace to erase, confirm to submit\n\
+         Back: to the title screen, or to the menu from the title screen"
+    }
+
+    fn settings(&self) -> Vec<Setting> {
+        let index = |found: Option<usize>| found.unwrap_or(0);
+        let time = |key, label, millis, min_ms, max_ms, step_ms| {
+            Setting::duration(
+                key,
+                label,
+                Duration::from_millis(millis),
+                Duration::from_millis(min_ms),
+                Duration::from_millis(max_ms),
+                Duration::from_millis(step_ms),
+            )
+        };
+
+        vec![
+            Setting::choice(
+                "alphabet",
+                "Symbols",
+                Alphabet::ALL.iter().map(Alphabet::name).collect(),
+                index(Alphabet::ALL.iter().position(|a| *a == self.alphabet)),
+            ),
+            Setting::choice(
+                "variant",
+                "Variant",
+                Variant::ALL.iter().map(Variant::name).collect(),
+                index(Variant::ALL.iter().position(|v| *v == self.variant)),
+            ),
+            Setting::choice(
+                "grouping",
+                "Grouping",
+                GROUPINGS.iter().map(|size| grouping_name(*size)).collect(),
+                index(GROUPINGS.iter().position(|size| *size == self.group_size)),
+            ),
+            Setting::bool("practice", "Practice", self.practice),
+            time("base_time", "Shown for at least", self.display_time.base_ms, MIN_BASE_MS, 10_000, 100),
+            time("per_symbol", "Extra time per symbol", self.display_time.per_symbol_ms, 0, MAX_PER_SYMBOL_MS, 100),
+            time("max_time", "Shown for at most", self.display_time.max_ms, 1_000, 60_000, 500),
+        ]
+    }
+
+    fn apply_settings(&mut self, values: &[Setting]) -> Result<(), String> {
+        let choice = |key| settings::value(values, key).and_then(|v| v.as_choice());
+        let millis = |key| settings::value(values, key).and_then(|v| v.as_duration()).map(|d| d.as_millis() as u64);
+
+        let mut display_time = self.display_time;
+        display_time.base_ms = millis("base_time").unwrap_or(display_time.base_ms);
+        display_time.per_symbol_ms = millis("per_symbol").unwrap_or(display_time.per_symbol_ms);
+        display_time.max_ms = millis("max_time").unwrap_or(display_time.max_ms);
+        if display_time.max_ms < display_time.base_ms + display_time.per_symbol_ms {
+            return Err("The longest display time is shorter than the first number's".to_string());
+        }
+
+        if let Some(index) = choice("alphabet") {
+            self.alphabet = Alphabet::ALL[index];
+        }
+        if let Some(index) = choice("variant") {
+            self.variant = Variant::ALL[index];
+        }
+        if let Some(index) = choice("grouping") {
+            self.group_size = GROUPINGS[index];
+        }
+        if let Some(practice) = settings::value(values, "practice").and_then(|v| v.as_bool()) {
+            self.practice = practice;
+        }
+        self.display_time = display_time;
+        self.showing_duration = display_time.for_level(self.level);
+        self.save_config();
+        Ok(())
+    }
+
+    fn handle_input(&mut self, key_event: KeyEvent, keys: &KeyMap) {
+        if let Some(names) = self.names.as_mut() {
+            match names.handle_key(key_event) {
+                Some(true) => {
+                    let names = names.names();
+                    self.reset_game();
+                    self.hot_seat = Some(HotSeat::new(names, None));
+                    self.state = GameState::Turn;
+                }
+                Some(false) => self.reset_game(),
+                None => {}
+            }
+            return;
+        }
+
+        if let Some(confirm) = self.confirm.as_mut() {
+            match confirm.handle_key(key_event, keys) {
+                Some(true) => {
+                    self.confirm = None;
+                    self.record_aborted();
+                    self.reset_game();
+                }
+                Some(false) => self.confirm = None,
+                None => {}
+            }
+            return;
+        }
+
+        // While answering, symbols of the alphabet are typed even if they are bound to an action
+        let typing = self.state == GameState::Waiting
+            && matches!(key_event.code, KeyCode::Char(c) if self.alphabet.symbol_for(c).is_some());
+
         let mut pressed = false;
-        match key_event.code {
-            KeyCode::Char('q') | KeyCode::Esc => match self.state {
+        if !typing && keys.is(Action::Back, &key_event) {
+            match self.state {
                 GameState::Title => self.quit = true,
+                _ if self.has_progress() => {
+                    self.confirm = Some(ConfirmDialog::new(format!(
+                        "Give up this run? You are on level {}.",
+                        self.level
+                    )));
+                    pressed = true;
+                }
                 _ => {
                     self.reset_game();
                     pressed = true;
                 },
-            },
-            _ => {}
+            }
         }
         if pressed {
             return;
         }
         match self.state {
-            GameState::Title => self.show_number(),
+            GameState::Title => match keys.action(&key_event, &[Action::Up, Action::Down, Action::Left, Action::Right]) {
+                Some(Action::Up) => self.select_alphabet(false),
+                Some(Action::Down) => self.select_alphabet(true),
+                Some(direction) => self.toggle_grouping(direction == Action::Right),
+                None => match key_event.code {
+                    KeyCode::Tab => {
+                        self.practice = !self.practice;
+                        self.save_config();
+                    }
+                    KeyCode::Char('v') => self.select_variant(),
+                    KeyCode::Char('t') => self.start_daily(),
+                    KeyCode::Char('p') => {
+                        let current = profile::current().map(|profile| profile.name);
+                        self.names = Some(NameEntry::new(current));
+                        self.state = GameState::Players;
+                    }
+                    _ => self.show_number(),
+                },
+            },
             GameState::Showing => {
                 // No input is handled during the showing state
             }
+            GameState::Operation => {
+                let said_true = match (key_event.code, keys.action(&key_event, &[Action::Left, Action::Right])) {
+                    (KeyCode::Char('y'), _) | (_, Some(Action::Left)) => true,
+                    (KeyCode::Char('n'), _) | (_, Some(Action::Right)) => false,
+                    _ => return,
+                };
+                if let Some(operation) = self.operation.take() {
+                    self.operations_checked += 1;
+                    if operation.is_true == said_true {
+                        self.operations_correct += 1;
+                    }
+                }
+                self.state = GameState::Waiting;
+            }
             GameState::Waiting => match key_event.code {
-                KeyCode::Enter => {
+                _ if !typing && keys.is(Action::Confirm, &key_event) => {
                     // Get input from the input bar
                     let equal = self
                         .answer
                         .as_ref()
-                        .zip(self.number.as_ref())
-                        .map(|(s1, s2)| s1 == s2)
+                        .zip(self.expected_answer())
+                        .map(|(s1, s2)| *s1 == s2)
                         .unwrap_or(false);
+                    self.trials.push(Trial {
+                        item: self.number.clone(),
+                        answer: self.answer.clone(),
+                        correct: Some(equal),
+                        ms: None,
+                    });
 
-                    if equal {
+                    if self.hot_seat.is_some() {
+                        self.end_turn(equal);
+                    } else if equal {
                         self.level += 1;
                         self.state = GameState::Success;
+                    } else if self.practice {
+                        self.practice_misses += 1;
+                        self.state = GameState::Review;
                     } else {
                         self.state = GameState::End;
+                        self.record_run();
                     }
                 }
                 KeyCode::Backspace => {
-                    match &mut self.answer {
-                        Some(ans) => {
-                            ans.pop();
-                            ()
-                        } // Pop from the string and do nothing with the result
-                        None => (), // Do nothing if there is no answer
+                    // Pop from the string and do nothing with the result
+                    if let Some(ans) = &mut self.answer {
+                        ans.pop();
                     }
                 }
-                KeyCode::Char(c) => match &mut self.answer {
-                    Some(ans) => {
-                        if c.is_numeric() {
-                            ans.push(c);
-                        }
+                // The rest of a burst keeps being thrown out until the keys slow down
+                KeyCode::Char(_) if self.typed_too_fast() => {
+                    self.answer = Some(String::new());
+                    self.rejected = true;
+                }
+                KeyCode::Char(c) => {
+                    if let (Some(ans), Some(symbol)) = (&mut self.answer, self.alphabet.symbol_for(c)) {
+                        ans.push(symbol);
                     }
-                    None => (),
-                },
+                }
                 _ => {}
             },
-            GameState::Success => self.show_number(),
-            GameState::End => self.reset_game(),
+            GameState::Success | GameState::Review => self.show_number(),
+            GameState::End | GameState::Standings => self.reset_game(),
+            GameState::Turn => self.start_turn(),
+            // Names are typed in `NameEntry`, handled above
+            GameState::Players => {}
+        }
+    }
+
+    fn on_event(&mut self, event: Event, keys: &KeyMap) {
+        match event {
+            // Terminals with bracketed paste send pasted text in one piece, answers are typed
+            Event::Paste(_) if self.state == GameState::Waiting => self.rejected = true,
+            Event::Key(key_event) if key_event.kind == KeyEventKind::Press => self.handle_input(key_event, keys),
+            _ => {}
         }
     }
 
-    fn run(&mut self, terminal: &mut Terminal<CrosstermBackend<Stdout>>) -> io::Result<()> {
-        self.init_game();
+    fn update(&mut self, dt: Duration) {
+        // Nothing moves while the player is asked whether to give up
+        if self.confirm.is_some() {
+            return;
+        }
 
-        while !self.quit {
-            terminal
-                .draw(|frame| match self.state {
-                    GameState::Title => self.render_title_screen(frame),
-                    GameState::Showing => self.render_showing_screen(frame),
-                    GameState::Waiting => self.render_waiting_screen(frame),
-                    GameState::Success => self.render_success_screen(frame),
-                    GameState::End => self.render_end_screen(frame),
-                })
-                .expect("Failed to render game");
+        if self.state == GameState::Showing {
+            self.shown_for += dt;
+            self.check_to_change_waiting();
+        }
+    }
 
-            if self.state != GameState::Showing {
-                self.handle_events()?;
-            } else {
-                self.check_to_change_waiting();
+    fn render(&self, frame: &mut Frame) {
+        match self.state {
+            GameState::Title => self.render_title_screen(frame),
+            GameState::Showing => self.render_showing_screen(frame),
+            GameState::Operation => self.render_operation_screen(frame),
+            GameState::Waiting => self.render_waiting_screen(frame),
+            GameState::Success => self.render_success_screen(frame),
+            GameState::Review => self.render_review_screen(frame),
+            GameState::End => self.render_end_screen(frame),
+            GameState::Players => {
+                let lines = self.names.as_ref().map(|names| names.lines(Color::Black)).unwrap_or_default();
+                self.render_centered(frame, lines);
+            }
+            GameState::Turn => {
+                if let Some(hot_seat) = &self.hot_seat {
+                    let detail = format!("{} symbols to remember", hot_seat.current().score + 1);
+                    self.render_centered(frame, hot_seat.turn_lines(&detail, Color::Black));
+                }
+            }
+            GameState::Standings => {
+                if let Some(hot_seat) = &self.hot_seat {
+                    self.render_centered(frame, hot_seat.standings_lines(self.score_unit(), Color::Black));
+                }
             }
         }
 
+        if let Some(confirm) = &self.confirm {
+            confirm.render(frame);
+        }
+    }
+
+    fn wants_exit(&self) -> bool {
+        self.quit
+    }
+
+    // Redraw the gauge at a steady pace while the number is shown
+    fn set_keys(&mut self, keys: &KeyMap) {
+        self.keys = keys.clone();
+    }
+
+    fn wake_in(&self) -> Option<Duration> {
+        (self.state == GameState::Showing).then(|| self.showing_duration.saturating_sub(self.shown_for))
+    }
+
+    fn is_running(&self) -> bool {
+        !matches!(self.state, GameState::Title | GameState::End | GameState::Players | GameState::Standings)
+    }
+
+    fn can_restart(&self) -> bool {
+        !self.daily
+    }
+
+    fn restart(&mut self) {
+        self.record_aborted();
+        self.reset_game();
+        self.show_number();
+    }
+
+    fn on_exit(&mut self) {
+        self.record_aborted();
         self.quit_game();
-        Ok(())
     }
 }
 
 impl NumberMemory {
     pub fn new() -> Self {
+        let config = Self::load_config();
+
         Self {
             state: GameState::Title,
             number: None,
             answer: None,
+            last_typed: None,
+            quick_gaps: 0,
+            rejected: false,
             level: 1,
             quit: false,
-            show_start: None,
-            showing_duration: Duration::from_millis(1700),
+            shown_for: Duration::ZERO,
+            showing_duration: config.display_time.for_level(1),
+            display_time: config.display_time,
+            practice: config.practice,
+            practice_misses: 0,
+            alphabet: config.alphabet,
+            variant: config.variant,
+            operation: None,
+            operations_checked: 0,
+            operations_correct: 0,
+            group_size: config.group_size,
+            confirm: None,
+            keys: KeyMap::default(),
+            reference: None,
+            trials: Vec::new(),
+            names: None,
+            hot_seat: None,
+            rng: GameRng::new(),
+            daily: false,
+            daily_status: DailyStatus::load(GAME_ID, Ranking::HigherIsBetter),
         }
     }
 
+    fn load_config() -> NumberMemoryConfig {
+        let mut config: NumberMemoryConfig = storage::load(CONFIG_FILE);
+        // A hand-edited file could ask for chunks of no symbols or a number that is never shown
+        if !GROUPINGS.contains(&config.group_size) {
+            config.group_size = NumberMemoryConfig::default().group_size;
+        }
+        let display_time = &mut config.display_time;
+        display_time.base_ms = display_time.base_ms.max(MIN_BASE_MS);
+        display_time.per_symbol_ms = display_time.per_symbol_ms.min(MAX_PER_SYMBOL_MS);
+        display_time.max_ms = display_time.max_ms.max(display_time.base_ms);
+        config
+    }
+
     fn render_title_screen(&self, frame: &mut Frame) {
-        let lines = vec![
+        let mut lines = vec![
             line_with_color("Number Memory", Color::White)
                 .style(Style::default().add_modifier(Modifier::BOLD)),
             line_with_color(
                 "The average person can remember 7 numbers at once.Can you do more?",
                 Color::White,
             ),
+            line_with_color("", Color::White),
+            line_with_color(format!("Symbols: ▲ {} ▼", self.alphabet.name()), Color::White),
+            line_with_color(format!("Variant: {}", self.variant.name()), Color::White),
+            line_with_color(
+                match self.group_size {
+                    Some(size) => format!("Grouping: ◀ chunks of {size} ▶"),
+                    None => "Grouping: ◀ none ▶".to_string(),
+                },
+                Color::White,
+            ),
+            line_with_color(
+                format!("Practice: {}", if self.practice { "on" } else { "off" }),
+                Color::White,
+            ),
+            line_with_color(
+                format!(
+                    "Shown for {:.1} s, +{:.1} s per symbol",
+                    self.display_time.for_level(1).as_secs_f64(),
+                    self.display_time.per_symbol_ms as f64 / 1000.0
+                ),
+                Color::White,
+            ),
+            line_with_color(
+                "↑ ↓ to change symbols • ← → to change grouping • v for variant • Tab for practice • p for pass and play • any other key to start",
+                Color::White,
+            ),
         ];
+        lines.push(line_with_color("", Color::White));
+        lines.extend(self.daily_status.lines(self.score_unit(), Color::White));
 
         let size = frame.area();
 
@@ -218,7 +780,11 @@ impl NumberMemory {
             ])
             .split(vertical_layout[1]);
 
-        let status_message = self.number.as_deref().unwrap_or("No number available");
+        let status_message = self
+            .number
+            .as_deref()
+            .map(|number| self.grouped(number))
+            .unwrap_or_else(|| "No number available".to_string());
 
         let message_paragraph = Paragraph::new(Line::from(Span::styled(
             status_message,
@@ -232,14 +798,11 @@ impl NumberMemory {
         frame.render_widget(message_paragraph, content_layout[1]);
 
         // Step 5: Calculate remaining progress (reversed)
-        let percent_remaining = self
-            .show_start
-            .map(|start| {
-                let elapsed = start.elapsed().as_secs_f64();
-                let total = self.showing_duration.as_secs_f64();
-                ((total - elapsed) / total).clamp(0.0, 1.0)
-            })
-            .unwrap_or(1.0); // 100% if timer hasn't started
+        let percent_remaining = {
+            let elapsed = self.shown_for.as_secs_f64();
+            let total = self.showing_duration.as_secs_f64();
+            ((total - elapsed) / total).clamp(0.0, 1.0)
+        };
 
         // Step 6: Horizontal layout to center the gauge
         let gauge_row = content_layout[3];
@@ -290,13 +853,17 @@ impl NumberMemory {
             .add_modifier(Modifier::BOLD);
 
         let texts = [
-            "What was the number?",
-            "Press enter to submit",
-            self.answer.as_deref().unwrap_or(""), // Option<String> -> &str
+            self.variant.prompt().to_string(),
+            if self.rejected {
+                "Pasted or typed faster than anyone can, type it yourself".to_string()
+            } else {
+                "Press enter to submit".to_string()
+            },
+            self.grouped(self.answer.as_deref().unwrap_or("")), // Option<String> -> &str
         ];
 
         for (i, text) in texts.iter().enumerate() {
-            let paragraph = Paragraph::new(Line::from(Span::styled(*text, text_style)))
+            let paragraph = Paragraph::new(Line::from(Span::styled(text.as_str(), text_style)))
                 .alignment(Alignment::Center);
             frame.render_widget(paragraph, vertical_chunks[i + 1]); // skip top padding
         }
@@ -331,11 +898,13 @@ impl NumberMemory {
             .add_modifier(Modifier::BOLD);
 
         // Step 3: Render all centered text lines
+        let number = self.grouped(self.number.as_deref().unwrap_or(""));
+        let answer = self.grouped(self.answer.as_deref().unwrap_or(""));
         let lines = [
             "Number",
-            self.number.as_deref().unwrap_or(""),
+            &number,
             "Your Answer",
-            self.answer.as_deref().unwrap_or(""),
+            &answer,
             "Level",
             &self.level.to_string(),
         ];
@@ -347,98 +916,349 @@ impl NumberMemory {
         }
     }
 
+    fn render_operation_screen(&self, frame: &mut Frame) {
+        let lines = vec![
+            line_with_color("Is this right?", Color::Black),
+            Line::from(""),
+            line_with_color(
+                self.operation.as_ref().map(|o| o.text.as_str()).unwrap_or(""),
+                Color::Black,
+            ),
+            Line::from(""),
+            line_with_color(
+                format!(
+                    "y / {} for yes • n / {} for no",
+                    self.keys.label(Action::Left),
+                    self.keys.label(Action::Right)
+                ),
+                Color::Black,
+            ),
+        ];
+
+        self.render_centered(frame, lines);
+    }
+
+    fn render_review_screen(&self, frame: &mut Frame) {
+        let [number, answer] = self.diff_lines();
+        let lines = vec![
+            line_with_color("Not quite, practice goes on", Color::Black),
+            Line::from(""),
+            number,
+            answer,
+            Self::diff_legend(),
+            Line::from(""),
+            line_with_color(
+                format!("Level {} • {} misses so far", self.level, self.practice_misses),
+                Color::Black,
+            ),
+            line_with_color("Press any key for a new number", Color::Black),
+        ];
+
+        self.render_centered(frame, lines);
+    }
+
     fn render_end_screen(&self, frame: &mut Frame) {
-        let size = frame.area(); // you used `area()` before but it's usually `size()`
+        let [number, answer] = self.diff_lines();
+        let label = match self.variant {
+            Variant::Backward => "Number, backwards",
+            Variant::Forward | Variant::Operation => "Number",
+        };
+        let mut lines = vec![
+            line_with_color(label, Color::Black),
+            number,
+            line_with_color("Your Answer", Color::Black),
+            answer,
+            Self::diff_legend(),
+            line_with_color("Level", Color::Black),
+            line_with_color(self.level.to_string(), Color::Black),
+        ];
+        if self.operations_checked > 0 {
+            lines.push(line_with_color(
+                format!(
+                    "{}/{} equations checked right",
+                    self.operations_correct, self.operations_checked
+                ),
+                Color::Black,
+            ));
+        }
+        let score = self.level - 1;
+        if let Some(reference) = &self.reference {
+            lines.push(line_with_color(
+                format!("Better than {:.0}% of people", reference.percentile(score, self.ranking())),
+                Color::Black,
+            ));
+        }
+
+        if self.daily {
+            lines.extend(self.daily_status.lines(self.score_unit(), Color::Black));
+        }
+
+        let below = self.render_centered(frame, lines);
+        if let Some(reference) = &self.reference {
+            render_distribution(frame, below, reference, score);
+        }
+    }
+
+    /// Draws the lines in the middle of the screen, returns the space left below them.
+    fn render_centered(&self, frame: &mut Frame, lines: Vec<Line>) -> Rect {
+        let size = frame.area();
 
-        // Step 1: Fill entire background with cyan
+        // Fill entire background with cyan
         let bg_block = Block::default().style(Style::default().bg(Color::Cyan));
         frame.render_widget(bg_block, size);
 
-        // Step 2: Vertically center 6 lines (3 labels + 3 values)
+        let height = lines.len() as u16;
         let vertical_chunks = Layout::default()
             .direction(Direction::Vertical)
             .constraints([
                 Constraint::Percentage(30),
-                Constraint::Length(1),
-                Constraint::Length(1),
-                Constraint::Length(1),
-                Constraint::Length(1),
-                Constraint::Length(1),
-                Constraint::Length(1),
+                Constraint::Length(height),
                 Constraint::Percentage(30),
             ])
             .split(size);
 
-        // Base style for labels and values
+        let paragraph = Paragraph::new(lines)
+            .style(Style::default().bg(Color::Cyan))
+            .alignment(Alignment::Center);
+        frame.render_widget(paragraph, vertical_chunks[1]);
+        vertical_chunks[2]
+    }
+
+    /// The number and the answer aligned symbol by symbol, mistakes highlighted in the answer.
+    fn diff_lines(&self) -> [Line<'static>; 2] {
         let base_style = Style::default()
             .fg(Color::Black)
             .bg(Color::Cyan)
             .add_modifier(Modifier::BOLD);
 
-        // Crossed-out style for the answer
-        let crossed_style = base_style.add_modifier(Modifier::CROSSED_OUT);
+        let cells = digit_diff::diff(
+            &self.expected_answer().unwrap_or_default(),
+            self.answer.as_deref().unwrap_or(""),
+        );
 
-        // Step 3: Render text with appropriate styles
-        let texts = [
-            ("Number", base_style),
-            (self.number.as_deref().unwrap_or(""), base_style),
-            ("Your Answer", base_style),
-            (self.answer.as_deref().unwrap_or(""), crossed_style), // crossed out!
-            ("Level", base_style),
-            (&self.level.to_string(), base_style),
-        ];
+        let mut number = Vec::new();
+        let mut answer = Vec::new();
+        for (i, cell) in cells.iter().enumerate() {
+            // Same chunks as when the number was shown
+            if self.group_size.is_some_and(|size| i > 0 && i % size == 0) {
+                number.push(Span::styled(" ", base_style));
+                answer.push(Span::styled(" ", base_style));
+            }
 
-        for (i, (text, style)) in texts.iter().enumerate() {
-            let paragraph = Paragraph::new(Line::from(Span::styled(*text, *style)))
-                .alignment(Alignment::Center);
-            frame.render_widget(paragraph, vertical_chunks[i + 1]); // skip top padding
+            number.push(Span::styled(cell.expected.unwrap_or(' ').to_string(), base_style));
+            answer.push(Span::styled(
+                cell.given.unwrap_or('_').to_string(),
+                Self::mark_style(cell.mark, base_style),
+            ));
+        }
+
+        [Line::from(number), Line::from(answer)]
+    }
+
+    fn mark_style(mark: Mark, base_style: Style) -> Style {
+        match mark {
+            Mark::Correct => base_style,
+            Mark::Transposed => base_style.bg(Color::Yellow),
+            Mark::Wrong | Mark::Missing => base_style.fg(Color::White).bg(Color::Red),
+            Mark::Extra => base_style
+                .fg(Color::White)
+                .bg(Color::Red)
+                .add_modifier(Modifier::CROSSED_OUT),
         }
     }
 
+    fn diff_legend() -> Line<'static> {
+        let base_style = Style::default().fg(Color::Black).bg(Color::Cyan);
+        Line::from(vec![
+            Span::styled(" wrong ", Self::mark_style(Mark::Wrong, base_style)),
+            Span::styled("  ", base_style),
+            Span::styled(" transposed ", Self::mark_style(Mark::Transposed, base_style)),
+        ])
+    }
+
     fn show_number(&mut self) {
         self.state = GameState::Showing;
-        self.show_start = Some(Instant::now());
+        self.shown_for = Duration::ZERO;
+        self.showing_duration = self.display_time.for_level(self.level);
         self.number = Some(self.generate_random_number());
-        self.answer = Some(String::new());    
+        self.answer = Some(String::new());
+        self.last_typed = None;
+        self.quick_gaps = 0;
+        self.rejected = false;
+    }
+
+    /// Notes a symbol typed into the answer, `true` while they come faster than anyone types.
+    fn typed_too_fast(&mut self) -> bool {
+        let now = replay::now();
+        let quick = self.last_typed.is_some_and(|at| now.duration_since(at) < MIN_TYPING_GAP);
+        self.last_typed = Some(now);
+        self.quick_gaps = if quick { self.quick_gaps + 1 } else { 0 };
+        self.quick_gaps >= BURST_GAPS
     }
 
     fn check_to_change_waiting(&mut self) {
-        if let Some(start_show) = self.show_start {
-            if start_show.elapsed() >= self.showing_duration {
+        // Keys pressed during the reveal were already handed to the Showing state, which
+        // ignores them, so they can't end up in the answer
+        if self.shown_for >= self.showing_duration {
+            if self.variant == Variant::Operation {
+                self.operation = Some(Operation::random(&mut self.rng));
+                self.state = GameState::Operation;
+            } else {
                 self.state = GameState::Waiting;
             }
         }
     }
 
-    // Generate a random valid number (no trailing 0)
-    fn generate_random_number(&self) -> String {
-        let mut rng = rand::rng();
+    // Generate a random valid number (no leading 0)
+    fn generate_random_number(&mut self) -> String {
+        let symbols = self.alphabet.symbols();
+        let (level, numeric) = (self.level, self.alphabet.is_numeric());
+
+        (0..level)
+            .map(|i| {
+                // symbols[0] is '0' for numeric alphabets, skip it as the first symbol of longer numbers
+                let start = usize::from(i == 0 && level > 1 && numeric);
+                char::from(symbols[self.rng.random_range(start..symbols.len())])
+            })
+            .collect()
+    }
+
+    /// What the player has to type for the current number.
+    fn expected_answer(&self) -> Option<String> {
+        let number = self.number.as_ref()?;
+        Some(match self.variant {
+            Variant::Backward => number.chars().rev().collect(),
+            Variant::Forward | Variant::Operation => number.clone(),
+        })
+    }
 
+    /// Splits a number in space separated chunks, when grouping is enabled.
+    fn grouped(&self, number: &str) -> String {
+        let Some(size) = self.group_size else {
+            return number.to_string();
+        };
 
-        // b'0'     gives you the ascii value of the character '0'
-        match self.level {
-            0 => String::new(),
-            1 => char::from(b'0' + rng.random_range(0..10)).to_string(),
-            _ => {
-                let first_digit = rng.random_range(1..10); // avoid leading zero
-                let rest: String = (1..self.level)
-                    .map(|_| char::from(b'0' + rng.random_range(0..10)))
-                    .collect();
-                format!("{}{}", first_digit, rest)
-            }
+        number
+            .chars()
+            .collect::<Vec<_>>()
+            .chunks(size)
+            .map(|chunk| chunk.iter().collect::<String>())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    fn select_alphabet(&mut self, forward: bool) {
+        let len = Alphabet::ALL.len();
+        let index = Alphabet::ALL.iter().position(|a| *a == self.alphabet).unwrap_or(0);
+        let next = if forward { index + 1 } else { index + len - 1 };
+        self.alphabet = Alphabet::ALL[next % len];
+        self.save_config();
+    }
+
+    fn select_variant(&mut self) {
+        let index = Variant::ALL.iter().position(|v| *v == self.variant).unwrap_or(0);
+        self.variant = Variant::ALL[(index + 1) % Variant::ALL.len()];
+        self.save_config();
+    }
+
+    /// Cycles between no grouping, chunks of 3 and chunks of 4.
+    fn toggle_grouping(&mut self, forward: bool) {
+        let index = GROUPINGS.iter().position(|o| *o == self.group_size).unwrap_or(0);
+        let next = if forward { index + 1 } else { index + GROUPINGS.len() - 1 };
+        self.group_size = GROUPINGS[next % GROUPINGS.len()];
+        self.save_config();
+    }
+
+    fn save_config(&self) {
+        let config = NumberMemoryConfig {
+            alphabet: self.alphabet,
+            variant: self.variant,
+            group_size: self.group_size,
+            practice: self.practice,
+            display_time: self.display_time,
+        };
+        // Forgetting the choice is not worth interrupting the game for
+        let _ = storage::save(CONFIG_FILE, &config);
+    }
+
+    /// Mode the results are stored under, forward span keeps the plain alphabet name.
+    fn mode_id(&self) -> String {
+        match self.variant {
+            Variant::Forward => self.alphabet.id().to_string(),
+            Variant::Backward => format!("backward_{}", self.alphabet.id()),
+            Variant::Operation => format!("operation_{}", self.alphabet.id()),
         }
     }
 
-    fn init_game(&mut self) {
-        self.answer = Some(String::new());
+    /// Records the run that just ended, the score is the longest number remembered,
+    /// and looks up how other people score for the end screen.
+    fn record_run(&mut self) {
+        // Losing a result is not worth interrupting the game for
+        let _ = history::record(&self.session_record());
+        self.reference = reference::distribution(GAME_ID, &self.mode_id());
+        if self.daily {
+            self.daily_status = DailyStatus::load(GAME_ID, self.ranking());
+        }
+    }
+
+    /// Whether giving up now would lose anything worth asking about.
+    fn has_progress(&self) -> bool {
+        match &self.hot_seat {
+            Some(hot_seat) => self.is_running() && (hot_seat.round > 1 || hot_seat.last_turn.is_some()),
+            None => self.is_running() && self.level > 1,
+        }
+    }
+
+    /// Keeps a trace of a run given up on, it never makes the leaderboard.
+    ///
+    /// Practice runs and pass-and-play games are never recorded, given up on or not.
+    fn record_aborted(&self) {
+        if self.has_progress() && !self.practice && self.hot_seat.is_none() {
+            let _ = history::record(&self.session_record().aborted());
+        }
+    }
+
+    fn session_record(&self) -> SessionRecord {
+        let mode = if self.daily { daily::MODE_ID.to_string() } else { self.mode_id() };
+        let mut record = SessionRecord::new(GAME_ID, &mode, self.level - 1).with_trials(self.trials.clone());
+        if let Some(size) = self.group_size {
+            record = record.with_metric("group_size", size as f64);
+        }
+        if self.operations_checked > 0 {
+            record = record.with_metric(
+                "operation_accuracy",
+                self.operations_correct as f64 / self.operations_checked as f64,
+            );
+        }
+        record
     }
 
     fn reset_common(&mut self) {
         self.state = GameState::Title;
+        self.confirm = None;
         self.answer = None;
         self.number = None;
-        self.show_start = None;
+        self.shown_for = Duration::ZERO;
         self.level = 1;
+        self.practice_misses = 0;
+        self.operation = None;
+        self.operations_checked = 0;
+        self.operations_correct = 0;
+        self.trials.clear();
+        self.names = None;
+        self.hot_seat = None;
+
+        if self.daily {
+            // Back to the player's own settings, and numbers nobody can predict
+            let config = Self::load_config();
+            self.alphabet = config.alphabet;
+            self.variant = config.variant;
+            self.practice = config.practice;
+            self.display_time = config.display_time;
+            self.daily = false;
+            self.rng = GameRng::new();
+            self.daily_status = DailyStatus::load(GAME_ID, self.ranking());
+        }
     }
 
     fn quit_game(&mut self) {
@@ -451,4 +1271,49 @@ impl NumberMemory {
         self.answer = Some(String::new());
     }
 
+    /// Starts today's challenge: decimal forward span with the default timing, and the numbers
+    /// drawn for everyone today.
+    fn start_daily(&mut self) {
+        if self.daily_status.played || !daily::start(GAME_ID) {
+            return;
+        }
+        self.reset_game();
+        self.daily = true;
+        self.alphabet = Alphabet::Decimal;
+        self.variant = Variant::Forward;
+        self.practice = false;
+        self.display_time = DisplayTime::default();
+        self.rng = daily::rng(GAME_ID);
+        self.show_number();
+    }
+
+    /// Shows the number of the player whose turn it is, at their own level.
+    fn start_turn(&mut self) {
+        if let Some(hot_seat) = &self.hot_seat {
+            self.level = hot_seat.current().score + 1;
+            self.show_number();
+        }
+    }
+
+    /// Scores the turn and passes the keyboard, or ends the game once everyone is out.
+    ///
+    /// A right answer adds a symbol to the player's next number, a wrong one knocks them out.
+    fn end_turn(&mut self, correct: bool) {
+        let expected = self.expected_answer().unwrap_or_default();
+        let Some(hot_seat) = self.hot_seat.as_mut() else {
+            return;
+        };
+
+        let player = hot_seat.current_mut();
+        player.out = !correct;
+        hot_seat.last_turn = Some(if correct {
+            player.score = self.level;
+            format!("{} remembered {} symbols", player.name, self.level)
+        } else {
+            format!("{} is out, it was {expected}", player.name)
+        });
+
+        self.state = if hot_seat.advance() { GameState::Turn } else { GameState::Standings };
+    }
+
 }
@@ -1,35 +1,172 @@
-use crate::games::r#trait::Game;
-use crate::games::utils::line_with_color;
-use crossterm::event;
+use crate::daily::{self, DailyStatus};
+use crate::games::duel::{self, Connection, Event, Message, Press, Role, Timed};
+use crate::games::r#trait::{Category, Game};
+use crate::games::settings::{self, Setting};
+use crate::games::utils::{line_with_color, render_distribution, ConfirmDialog};
+use crate::history::{self, Ranking, SessionRecord, Trial};
+use crate::keymap::{Action, KeyMap};
+use crate::profile;
+use crate::reference::{self, Distribution};
+use crate::replay::{self, GameRng};
+use crate::storage;
 use crossterm::event::{
-    KeyCode, KeyEvent,
+    KeyCode, KeyEvent, KeyModifiers,
 };
 use rand::Rng;
-use ratatui::backend::CrosstermBackend;
+use serde::{Deserialize, Serialize};
 use ratatui::layout::{Alignment, Constraint, Direction, Layout};
 use ratatui::style::{Color, Style};
+use ratatui::text::Line;
 use ratatui::widgets::{Block, Paragraph};
-use ratatui::Terminal;
+use ratatui::Frame;
 use std::time::{Duration, Instant};
-use std::{io, thread};
+
+/// Identifier of the game in the history, must not change.
+const GAME_ID: &str = "reaction";
+const CONFIG_FILE: &str = "reaction.json";
+/// Five attempts averaged with the default waits, anything else is stored as "custom".
+const MODE_ID: &str = "standard";
+const CUSTOM_MODE_ID: &str = "custom";
+/// Duels are ranked apart, the average covers fewer presses under different pressure.
+const DUEL_MODE_ID: &str = "duel";
+/// Most attempts a run can be set to.
+const MAX_ATTEMPTS: u32 = 20;
+
+/// Anything faster than this is anticipation, not a reaction to the stimulus.
+const MIN_HUMAN_REACTION_MS: u32 = 100;
+/// Presses of one key closer together than this are a held key repeating, on terminals that can't say so.
+const AUTO_REPEAT_GAP: Duration = Duration::from_millis(100);
+
+/// Time between two flashes of the calibration beat.
+const BEAT_INTERVAL: Duration = Duration::from_millis(750);
+/// How long each calibration flash stays on screen.
+const BEAT_FLASH: Duration = Duration::from_millis(120);
+/// Number of flashes in a calibration run.
+const BEAT_COUNT: u32 = 10;
+/// First flashes are ignored while the player finds the rhythm.
+const WARMUP_BEATS: u32 = 2;
+/// Upper bound for the latency correction, anything above is treated as a bad calibration.
+const MAX_INPUT_LATENCY_MS: i64 = 150;
+
+/// Rounds to win a duel.
+const DUEL_WINS: u32 = 3;
+/// Time to read the outcome of a round before the next one's random wait starts.
+const DUEL_BREAK: Duration = Duration::from_millis(1500);
+/// A player who hasn't pressed this long after green missed the round.
+const DUEL_ROUND_TIMEOUT: Duration = Duration::from_secs(3);
+/// How often the connection is checked during a duel.
+const DUEL_POLL: Duration = Duration::from_millis(10);
 
 enum GameState {
-    Title,        // Game is waiting for you to press any key to start
-    Waiting,      // Random delay is counting down (you should NOT press a key)
-    TooSoon,      // You pressed a key during the wait period — try again
-    Active,       // NOW! Press a key — the timer is running
-    Success(u32), // You pressed in time — the number is your reaction time (ms)
-    Stats(u32),   // The games is over — shows all times and the average
+    Title,                   // Game is waiting for you to press any key to start
+    Calibrating,             // Tap along with a steady beat to measure input and display latency
+    Calibrated(Option<u32>), // Calibration finished — the estimated latency, if it could be measured
+    Waiting,                 // Random delay is counting down (you should NOT press a key)
+    TooSoon,                 // You pressed a key during the wait period — try again
+    Active,                  // NOW! Press a key — the timer is running
+    Anticipated(u32),        // You pressed faster than humanly possible — the attempt is not counted
+    Success(u32),            // You pressed in time — the number is your reaction time (ms)
+    Stats(u32),              // The games is over — shows all times and the average
+    DuelSetup,               // Typing the address of the host to join, or nothing to host
+    DuelConnecting,          // Waiting for the other player to join, or for the host to answer
+    DuelWaiting,             // Duel round: red until the moment agreed with the other player
+    DuelActive,              // Duel round: green for both, first valid press wins
+    DuelPressed(Press),      // Own press sent, waiting for the other player's
+    DuelOver,                // The duel is won, lost or the connection closed
+}
+
+/// Tap-along calibration.
+///
+/// The screen flashes on a steady beat and the player presses in time with it. The beat is
+/// predictable, so there is no reaction involved: the typical gap between a flash being drawn
+/// and the press arriving is the latency of the terminal and input path.
+struct Calibration {
+    next_beat: Instant,           // When the next flash should be shown
+    flash_until: Option<Instant>, // When the current flash should be hidden
+    beats: u32,                   // How many flashes were shown so far
+    beat_drawn: Option<Instant>,  // When the current flash actually reached the terminal
+    last_tapped: u32,             // Last beat that got a tap, only the first tap per beat counts
+    offsets: Vec<i64>,            // Tap offsets from the drawn flash (ms, may be negative)
+}
+
+/// Reaction preferences, kept in the data directory between runs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+struct ReactionConfig {
+    /// Attempts averaged in a run.
+    attempts: u32,
+    /// Bounds of the random wait before the screen turns green.
+    min_wait_ms: u64,
+    max_wait_ms: u64,
+    /// Latency found by the last calibration, taken off every reaction.
+    input_latency_ms: u32,
+}
+
+impl Default for ReactionConfig {
+    fn default() -> Self {
+        Self {
+            attempts: 5,
+            min_wait_ms: 1800,
+            max_wait_ms: 4000,
+            input_latency_ms: 0,
+        }
+    }
+}
+
+impl ReactionConfig {
+    /// Whether runs follow the standard rules, the player's own latency aside.
+    fn is_standard(&self) -> bool {
+        let standard = Self { input_latency_ms: self.input_latency_ms, ..Self::default() };
+        *self == standard
+    }
+}
+
+/// A versus match against another rataplay over the network.
+///
+/// Both screens turn green at the same moment on the host's clock, and the host compares when
+/// the two presses landed on it.
+struct Duel {
+    connection: Connection,
+    opponent: String,
+    round: u32,
+    go_at: Option<Instant>,       // When the current round turns green, on our clock
+    press: Option<Timed>,         // What we did this round
+    other_press: Option<Timed>,   // What the other player did, on the host until it judges
+    wins: u32,
+    losses: u32,
+    last_round: Option<String>,   // How the previous round went
+    ended: Option<String>,        // Why the duel stopped early
+}
+
+/// Latency measurements used to make the reaction times trustworthy.
+#[derive(Default)]
+struct Latency {
+    input_ms: u32,          // Terminal and input latency estimated by calibration
+    render_total: Duration, // Total time spent drawing frames
+    render_frames: u32,     // Number of frames drawn
 }
 
 pub struct ReactionGame {
-    state: GameState,            // Current state (Ready, Waiting, etc.)
-    attempts: u32,               // Total number of attempts to make (default: 5)
-    done: u32,                   // How many attempts have been completed
-    reaction_history: Vec<u32>,  // Stores reaction times
-    start_time: Option<Instant>, // When the Active phase started
-    wait_until: Option<Instant>, // When the Waiting phase should end
-    quit: bool,                  // Whether the user wants to quit or not
+    state: GameState,                 // Current state (Ready, Waiting, etc.)
+    config: ReactionConfig,           // Number of attempts and wait bounds
+    done: u32,                        // How many attempts have been completed
+    reaction_history: Vec<u32>,       // Stores reaction times
+    flagged: Vec<u32>,                // Stores anticipated times that were excluded
+    trials: Vec<Trial>,               // Every timed press in order, anticipated ones included
+    start_time: Option<Instant>,      // When the Active frame was drawn
+    last_press: Option<(KeyCode, Instant)>, // Last key pressed and when, to tell auto-repeat apart
+    wait_left: Option<Duration>,      // How much of the Waiting phase is left
+    calibration: Option<Calibration>, // Running calibration, if any
+    latency: Latency,                 // Measured loop and terminal latency
+    confirm: Option<ConfirmDialog>,   // Asks before a run in progress is thrown away
+    reference: Option<Distribution>,  // How other people score in the mode of the last run
+    duel: Option<Duel>,               // Versus match over the network, if one is on
+    address: String,                  // Host address typed to join a duel
+    rng: GameRng,                      // Draws the waits
+    daily: bool,                      // Whether the run is today's challenge
+    daily_waits: Vec<u64>,            // Today's wait for each attempt, the same after a false start
+    daily_status: DailyStatus,        // Today's challenge, streak and best days
+    quit: bool,                       // Whether the user wants to quit or not
 }
 
 impl Game for ReactionGame {
@@ -41,13 +178,84 @@ impl Game for ReactionGame {
         "Test your visual reflexes"
     }
 
-    fn handle_input(&mut self, key_event: KeyEvent) {
+    fn id(&self) -> &str {
+        GAME_ID
+    }
+
+    fn category(&self) -> Category {
+        Category::Reflex
+    }
+
+    fn ranking(&self) -> Ranking {
+        Ranking::LowerIsBetter
+    }
+
+    fn score_unit(&self) -> &str {
+        "ms"
+    }
+
+    fn help(&self) -> &str {
+        "Wait for the screen to turn green, then press any key as fast as you can.\n\
+         Pressing while it is still red counts as too soon and the attempt starts over.\n\
+         Times under 100 ms are anticipations and are left out of the average.\n\
+         \n\
+         Any key: start / react / continue\n\
+         c (title screen): calibrate input latency by tapping along to the beat\n\
+         t (title screen): today's challenge, the same waits for everyone with the standard\n\
+         settings, one attempt a day\n\
+         v (title screen): duel another player over the network, first to 3 rounds.\n\
+         Leave the address empty to host, the other player types this machine's address.\n\
+         A false start hands the round to the other player.\n\
+         Back: to the title screen, or to the menu from the title screen"
+    }
+
+    fn handle_input(&mut self, key_event: KeyEvent, keys: &KeyMap) {
+        // Taken first so the time spent matching below is not counted
+        let now = replay::now();
+
+        if let Some(confirm) = self.confirm.as_mut() {
+            match confirm.handle_key(key_event, keys) {
+                Some(true) => {
+                    self.confirm = None;
+                    self.record_aborted();
+                    self.reset();
+                    self.state = GameState::Title;
+                }
+                Some(false) => {
+                    // The question stood in for a pause, pick up the same way
+                    self.confirm = None;
+                    self.on_resume();
+                }
+                None => {}
+            }
+            return;
+        }
+
+        if matches!(self.state, GameState::DuelSetup) {
+            self.handle_address(key_event);
+            return;
+        }
+
+        // A held key must not react for the player, even where it comes as a stream of presses
+        let repeated = self
+            .last_press
+            .is_some_and(|(code, at)| code == key_event.code && now.duration_since(at) < AUTO_REPEAT_GAP);
+        self.last_press = Some((key_event.code, now));
+        if repeated {
+            return;
+        }
+
+        // Every other key is a reaction, only Back is taken from the key map
+        let back = keys.is(Action::Back, &key_event);
         match key_event.code {
-            KeyCode::Char('q') | KeyCode::Esc => {
-                self.done = 0;
-                self.reaction_history.clear();
-                self.start_time = None;
-                self.wait_until = None;
+            _ if back && self.has_progress() => {
+                self.confirm = Some(ConfirmDialog::new(format!(
+                    "Give up this run? {} of {} attempts are done.",
+                    self.done, self.config.attempts
+                )));
+            }
+            _ if back => {
+                self.reset();
 
                 match self.state {
                     GameState::Title => self.quit = true,
@@ -57,163 +265,933 @@ impl Game for ReactionGame {
 
             _ => match self.state {
                 GameState::Title => {
-                    self.start_waiting();
+                    if key_event.code == KeyCode::Char('c') {
+                        self.start_calibration();
+                    } else if key_event.code == KeyCode::Char('v') {
+                        self.address.clear();
+                        self.state = GameState::DuelSetup;
+                    } else if key_event.code == KeyCode::Char('t') {
+                        self.start_daily();
+                    } else {
+                        self.start_waiting();
+                    }
+                }
+                GameState::Calibrating => {
+                    if let Some(calibration) = self.calibration.as_mut() {
+                        calibration.tap(now);
+                    }
+                }
+                GameState::Calibrated(_) => {
+                    self.state = GameState::Title;
                 }
                 GameState::Waiting => {
                     self.state = GameState::TooSoon;
-                    self.wait_until = None;
+                    self.wait_left = None;
                 }
-                GameState::TooSoon => {
+                GameState::TooSoon | GameState::Anticipated(_) => {
                     self.start_waiting();
                 }
                 GameState::Active => {
+                    // Ignore presses that arrive before the green frame reached the terminal
                     if let Some(start) = self.start_time {
-                        let duration = Instant::now().duration_since(start).as_millis() as u32;
-                        self.reaction_history.push(duration);
-                        self.done += 1;
-                        self.state = GameState::Success(duration);
+                        let raw = now.duration_since(start).as_millis() as u32;
+                        let duration = raw.saturating_sub(self.latency.input_ms);
+
+                        let counted = duration >= MIN_HUMAN_REACTION_MS;
+                        self.trials.push(Trial {
+                            correct: Some(counted),
+                            ms: Some(duration as f64),
+                            ..Trial::default()
+                        });
+
+                        if counted {
+                            self.reaction_history.push(duration);
+                            self.done += 1;
+                            self.state = GameState::Success(duration);
+                        } else {
+                            self.flagged.push(duration);
+                            self.state = GameState::Anticipated(duration);
+                        }
+                        self.start_time = None;
                     }
                 }
                 GameState::Success(_) => {
                     // Attempts left
-                    if self.done < self.attempts {
+                    if self.done < self.config.attempts {
                         self.start_waiting();
                     } else {
                         //
-                        let avg = self.reaction_history.iter().sum::<u32>() / self.attempts;
+                        let avg = self.reaction_history.iter().sum::<u32>() / self.config.attempts;
+                        // Losing a result is not worth interrupting the game for
+                        let _ = history::record(&self.session_record(avg));
+                        self.reference = reference::distribution(GAME_ID, self.mode_id());
+                        if self.daily {
+                            self.daily_status = DailyStatus::load(GAME_ID, self.ranking());
+                        }
 
                         self.state = GameState::Stats(avg);
                     }
                 }
                 GameState::Stats(_) => {
-                    self.done = 0;
-                    self.reaction_history.clear();
+                    self.reset();
                     self.state = GameState::Title;
                 }
+                GameState::DuelWaiting => self.duel_press(Press::FalseStart, now),
+                GameState::DuelActive => {
+                    if let Some(start) = self.start_time {
+                        let raw = now.duration_since(start).as_millis() as u32;
+                        let duration = raw.saturating_sub(self.latency.input_ms);
+                        self.trials.push(Trial {
+                            correct: Some(duration >= MIN_HUMAN_REACTION_MS),
+                            ms: Some(duration as f64),
+                            ..Trial::default()
+                        });
+
+                        if duration >= MIN_HUMAN_REACTION_MS {
+                            self.reaction_history.push(duration);
+                            self.duel_press(Press::Reaction(duration), now);
+                        } else {
+                            self.flagged.push(duration);
+                            self.duel_press(Press::FalseStart, now);
+                        }
+                    }
+                }
+                GameState::DuelOver => {
+                    self.reset();
+                    self.state = GameState::Title;
+                }
+                GameState::DuelSetup | GameState::DuelConnecting | GameState::DuelPressed(_) => {}
             },
         }
     }
 
-    fn run(&mut self, terminal: &mut Terminal<CrosstermBackend<io::Stdout>>) -> io::Result<()> {
-        while !self.quit {
-            terminal
-                .draw(|frame| {
-                    let (color, lines) = match self.state {
-                        GameState::Title => (
-                            Color::Blue,
-                            vec![
-                                line_with_color("⚡", Color::White),
-                                line_with_color(
-                                    "When the red box turns green, press as quickly as you can",
-                                    Color::White,
-                                ),
-                                line_with_color("Press any button to start", Color::White),
-                            ],
+    fn settings(&self) -> Vec<Setting> {
+        let wait = |key, label, millis| {
+            Setting::duration(
+                key,
+                label,
+                Duration::from_millis(millis),
+                Duration::from_millis(500),
+                Duration::from_secs(10),
+                Duration::from_millis(100),
+            )
+        };
+
+        vec![
+            Setting::int("attempts", "Attempts", self.config.attempts as i64, 1, MAX_ATTEMPTS as i64),
+            wait("min_wait", "Shortest wait", self.config.min_wait_ms),
+            wait("max_wait", "Longest wait", self.config.max_wait_ms),
+        ]
+    }
+
+    fn apply_settings(&mut self, values: &[Setting]) -> Result<(), String> {
+        let mut config = self.config;
+        if let Some(attempts) = settings::value(values, "attempts").and_then(|v| v.as_int()) {
+            config.attempts = attempts as u32;
+        }
+        if let Some(wait) = settings::value(values, "min_wait").and_then(|v| v.as_duration()) {
+            config.min_wait_ms = wait.as_millis() as u64;
+        }
+        if let Some(wait) = settings::value(values, "max_wait").and_then(|v| v.as_duration()) {
+            config.max_wait_ms = wait.as_millis() as u64;
+        }
+
+        if config.min_wait_ms > config.max_wait_ms {
+            return Err("The shortest wait can't be longer than the longest one".to_string());
+        }
+
+        self.config = config;
+        // Not being able to keep them only matters next launch, they apply to this one anyway
+        let _ = storage::save(CONFIG_FILE, &self.config);
+        Ok(())
+    }
+
+    fn update(&mut self, dt: Duration) {
+        // Nothing moves while the player is asked whether to give up
+        if self.confirm.is_some() {
+            return;
+        }
+
+        if self.duel.is_some() {
+            self.update_duel();
+            return;
+        }
+
+        match self.state {
+            GameState::Waiting => {
+                if let Some(left) = self.wait_left {
+                    if left <= dt {
+                        self.state = GameState::Active;
+                        // The timer starts once the green frame has been drawn, see `on_frame_drawn`
+                        self.start_time = None;
+                        self.wait_left = None;
+                    } else {
+                        self.wait_left = Some(left - dt);
+                    }
+                }
+            }
+            GameState::Calibrating => {
+                if let Some(calibration) = self.calibration.as_mut() {
+                    calibration.update(replay::now());
+
+                    if calibration.is_finished() {
+                        let measured = calibration.latency_ms();
+                        if let Some(ms) = measured {
+                            self.latency.input_ms = ms;
+                            self.save_latency(ms);
+                        }
+                        self.calibration = None;
+                        self.state = GameState::Calibrated(measured);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn render(&self, frame: &mut Frame) {
+        let (color, lines) = match self.state {
+            GameState::Title => {
+                let mut lines = vec![
+                    line_with_color("⚡", Color::White),
+                    line_with_color(
+                        "When the red box turns green, press as quickly as you can",
+                        Color::White,
+                    ),
+                    line_with_color("Press any button to start", Color::White),
+                    line_with_color(
+                        format!(
+                            "Press c to calibrate (current correction: {} ms)",
+                            self.latency.input_ms
                         ),
-                        GameState::Waiting => (
-                            Color::Red,
-                            vec![line_with_color("Wait for green", Color::White)],
+                        Color::White,
+                    ),
+                    line_with_color("Press v for a duel with another player on the network", Color::White),
+                    Line::from(""),
+                ];
+                lines.extend(self.daily_status.lines(self.score_unit(), Color::White));
+                (Color::Blue, lines)
+            }
+            GameState::Calibrating => {
+                let (flashing, beat) = self
+                    .calibration
+                    .as_ref()
+                    .map(|c| (c.flash_until.is_some(), c.beats))
+                    .unwrap_or((false, 0));
+                (
+                    if flashing { Color::Green } else { Color::DarkGray },
+                    vec![
+                        line_with_color(
+                            "Press any key in time with the green flashes",
+                            Color::White,
                         ),
-                        GameState::TooSoon => (
-                            Color::LightBlue,
-                            vec![
-                                line_with_color("Too soon!", Color::White),
-                                line_with_color("Try again by pressing a button", Color::White),
-                            ],
+                        line_with_color(
+                            format!("Beat {beat}/{BEAT_COUNT}"),
+                            Color::White,
                         ),
-                        GameState::Active => (
-                            Color::Green,
-                            vec![line_with_color("Press now!", Color::White)],
+                    ],
+                )
+            }
+            GameState::Calibrated(latency) => (
+                Color::Blue,
+                vec![
+                    match latency {
+                        Some(ms) => line_with_color(
+                            format!("Input and display latency: {ms} ms"),
+                            Color::White,
                         ),
-                        GameState::Success(i) => (
-                            Color::Cyan,
-                            vec![
-                                line_with_color(format!("{i} ms"), Color::White),
-                                line_with_color("Keep going! Press to continue", Color::White),
-                            ],
+                        None => line_with_color(
+                            "Not enough taps in time with the beat, calibration unchanged",
+                            Color::White,
                         ),
-                        GameState::Stats(avg) => (
-                            Color::Cyan,
-                            vec![
-                                line_with_color("Average reaction time", Color::White),
-                                line_with_color(format!("{avg} ms"), Color::White),
-                            ],
+                    },
+                    line_with_color(
+                        format!("Average frame render time: {} ms", self.latency.render_ms()),
+                        Color::White,
+                    ),
+                    line_with_color("Press any button to continue", Color::White),
+                ],
+            ),
+            GameState::Waiting => (
+                Color::Red,
+                vec![line_with_color("Wait for green", Color::White)],
+            ),
+            GameState::TooSoon => (
+                Color::LightBlue,
+                vec![
+                    line_with_color("Too soon!", Color::White),
+                    line_with_color("Try again by pressing a button", Color::White),
+                ],
+            ),
+            GameState::Active => (
+                Color::Green,
+                vec![line_with_color("Press now!", Color::White)],
+            ),
+            GameState::Anticipated(i) => (
+                Color::LightBlue,
+                vec![
+                    line_with_color(format!("{i} ms is too fast to be a reaction"), Color::White),
+                    line_with_color("This attempt is not counted", Color::White),
+                    line_with_color("Try again by pressing a button", Color::White),
+                ],
+            ),
+            GameState::Success(i) => (
+                Color::Cyan,
+                vec![
+                    line_with_color(format!("{i} ms"), Color::White),
+                    line_with_color("Keep going! Press to continue", Color::White),
+                ],
+            ),
+            GameState::Stats(avg) => {
+                let mut lines = vec![
+                    line_with_color("Average reaction time", Color::White),
+                    line_with_color(format!("{avg} ms"), Color::White),
+                    line_with_color(
+                        format!(
+                            "Corrected by {} ms of input latency • {} ms render time",
+                            self.latency.input_ms,
+                            self.latency.render_ms()
                         ),
-                    };
-
-                    let size = frame.area();
-
-                    // Background fill
-                    let background = Block::default().style(Style::default().bg(color));
-                    frame.render_widget(background, size);
-
-                    // Layout to vertically center
-                    let chunks = Layout::default()
-                        .direction(Direction::Vertical)
-                        .constraints([
-                            Constraint::Percentage(40),
-                            Constraint::Min(3),
-                            Constraint::Percentage(40),
-                        ])
-                        .split(size);
-
-                    let paragraph: Paragraph<'_> = Paragraph::new(lines)
-                        .alignment(Alignment::Center)
-                        .block(Block::default());
-
-                    frame.render_widget(paragraph, chunks[1]);
-                })
-                .expect("Error while rendering game");
-            
-            match self.state {
-                GameState::Active | GameState::Waiting => {
-                    if event::poll(Duration::from_millis(10))? {
-                        self.handle_events()?;
+                        Color::White,
+                    ),
+                ];
+                if !self.flagged.is_empty() {
+                    lines.push(line_with_color(
+                        format!(
+                            "{} anticipated press(es) under {MIN_HUMAN_REACTION_MS} ms excluded",
+                            self.flagged.len()
+                        ),
+                        Color::White,
+                    ));
+                }
+                if let Some(reference) = &self.reference {
+                    lines.push(line_with_color(
+                        format!("Faster than {:.0}% of people", reference.percentile(avg, self.ranking())),
+                        Color::White,
+                    ));
+                }
+                if self.daily {
+                    lines.extend(self.daily_status.lines(self.score_unit(), Color::White));
+                }
+                (Color::Cyan, lines)
+            }
+            GameState::DuelSetup => (
+                Color::Blue,
+                vec![
+                    line_with_color("⚔ Duel", Color::White),
+                    line_with_color(format!("Host's address: {}▏", self.address), Color::White),
+                    line_with_color(
+                        format!("Type it to join, or leave it empty and press Enter to host on port {}", duel::DUEL_PORT),
+                        Color::White,
+                    ),
+                    line_with_color("Esc to go back", Color::White),
+                ],
+            ),
+            GameState::DuelConnecting => {
+                let waiting = match self.duel.as_ref().map(|duel| duel.connection.role()) {
+                    Some(Role::Host) => format!("Waiting for a player to join on port {}…", duel::DUEL_PORT),
+                    _ => format!("Connecting to {}…", self.address.trim()),
+                };
+                (Color::Blue, vec![line_with_color(waiting, Color::White)])
+            }
+            GameState::DuelWaiting => (Color::Red, self.duel_lines("Wait for green")),
+            GameState::DuelActive => (Color::Green, vec![line_with_color("Press now!", Color::White)]),
+            GameState::DuelPressed(press) => {
+                let opponent = self.duel.as_ref().map_or("", |duel| duel.opponent.as_str());
+                let mut lines = vec![match press {
+                    Press::Reaction(ms) => line_with_color(format!("{ms} ms"), Color::White),
+                    Press::FalseStart => {
+                        line_with_color(format!("Too soon! The round goes to {opponent}"), Color::White)
                     }
+                    Press::Missed => line_with_color("Too slow, no press this round", Color::White),
+                }];
+                lines.push(line_with_color(format!("Waiting for {opponent}…"), Color::White));
+                (Color::LightBlue, lines)
+            }
+            GameState::DuelOver => {
+                let headline = match self.duel.as_ref() {
+                    Some(duel) if duel.ended.is_some() => duel.ended.clone().unwrap_or_default(),
+                    Some(duel) if duel.wins >= DUEL_WINS => "You won the duel!".to_string(),
+                    Some(duel) => format!("{} won the duel", duel.opponent),
+                    None => String::new(),
+                };
+                let mut lines = self.duel_lines(&headline);
+                if !self.reaction_history.is_empty() {
+                    let average = self.reaction_history.iter().sum::<u32>() / self.reaction_history.len() as u32;
+                    lines.push(line_with_color(format!("Your average: {average} ms"), Color::White));
+                }
+                lines.push(line_with_color("Press any button to continue", Color::White));
+                (Color::Cyan, lines)
+            }
+        };
+
+        let size = frame.area();
+
+        // Background fill
+        let background = Block::default().style(Style::default().bg(color));
+        frame.render_widget(background, size);
+
+        // Layout to vertically center
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Percentage(40),
+                Constraint::Min(3),
+                Constraint::Percentage(40),
+            ])
+            .split(size);
+
+        let paragraph: Paragraph<'_> = Paragraph::new(lines)
+            .alignment(Alignment::Center)
+            .block(Block::default());
+
+        frame.render_widget(paragraph, chunks[1]);
+
+        if let (GameState::Stats(avg), Some(reference)) = (&self.state, &self.reference) {
+            render_distribution(frame, chunks[2], reference, *avg);
+        }
+
+        if let Some(confirm) = &self.confirm {
+            confirm.render(frame);
+        }
+    }
+
+    fn wants_exit(&self) -> bool {
+        self.quit
+    }
+
+    // Only the waiting and calibration screens change on their own, everything else waits for a key
+    fn wake_in(&self) -> Option<Duration> {
+        // The other player's messages don't wake the loop, so look for them often
+        if self.duel.is_some() {
+            return Some(DUEL_POLL);
+        }
+
+        match self.state {
+            GameState::Waiting => self.wait_left,
+            GameState::Calibrating => self
+                .calibration
+                .as_ref()
+                .map(|calibration| calibration.next_deadline().saturating_duration_since(replay::now())),
+            _ => None,
+        }
+    }
 
-                    self.update();
+    fn on_frame_drawn(&mut self, at: Instant, draw_time: Duration) {
+        self.latency.render_total += draw_time;
+        self.latency.render_frames += 1;
 
-                    thread::sleep(Duration::from_millis(5));
-                },
-                _ => self.handle_events()?,
+        match self.state {
+            GameState::Active | GameState::DuelActive if self.start_time.is_none() => self.start_time = Some(at),
+            GameState::Calibrating => {
+                if let Some(calibration) = self.calibration.as_mut() {
+                    calibration.flash_drawn(at);
+                }
             }
+            _ => {}
         }
+    }
+
+    fn is_running(&self) -> bool {
+        // A duel can't be paused, the other player's clock keeps running
+        self.duel.is_none() && !matches!(self.state, GameState::Title | GameState::Calibrated(_) | GameState::Stats(_))
+    }
+
+    fn on_resume(&mut self) {
+        match self.state {
+            // The green screen was up during the pause, timing it now would count the pause
+            GameState::Active => self.start_waiting(),
+            // The beat runs on the wall clock, tapping along has to start over
+            GameState::Calibrating => self.start_calibration(),
+            _ => {}
+        }
+    }
 
+    fn can_restart(&self) -> bool {
+        !self.daily
+    }
+
+    fn restart(&mut self) {
+        self.record_aborted();
+        self.reset();
+        self.start_waiting();
+    }
+
+    fn on_exit(&mut self) {
+        self.record_aborted();
+        self.reset();
+        self.state = GameState::Title;
         self.quit = false; // Reset;
-        Ok(())
     }
 }
 
 impl ReactionGame {
     pub fn new() -> Self {
+        let config = Self::load_config();
         Self {
             state: GameState::Title,
-            attempts: 5, // Add a better way to make defaults later
+            config,
             done: 0,
             reaction_history: Vec::new(),
+            flagged: Vec::new(),
+            trials: Vec::new(),
             start_time: None,
-            wait_until: None,
+            last_press: None,
+            wait_left: None,
+            calibration: None,
+            latency: Latency { input_ms: config.input_latency_ms, ..Latency::default() },
+            confirm: None,
+            reference: None,
+            duel: None,
+            address: String::new(),
+            rng: GameRng::new(),
+            daily: false,
+            daily_waits: Vec::new(),
+            daily_status: DailyStatus::load(GAME_ID, Ranking::LowerIsBetter),
             quit: false,
         }
     }
 
-    pub fn update(&mut self) {
-        if let GameState::Waiting = self.state {
-            if let Some(when) = self.wait_until {
-                if Instant::now() >= when {
-                    self.state = GameState::Active;
-                    self.start_time = Some(Instant::now());
-                    self.wait_until = None;
-                }
-            }
+    fn load_config() -> ReactionConfig {
+        let mut config: ReactionConfig = storage::load(CONFIG_FILE);
+        // A hand-edited file could ask for no attempts at all or a wait that ends before it starts
+        config.attempts = config.attempts.clamp(1, MAX_ATTEMPTS);
+        config.max_wait_ms = config.max_wait_ms.max(config.min_wait_ms);
+        config
+    }
+
+    /// Starts today's challenge: standard settings and the waits drawn for everyone today.
+    fn start_daily(&mut self) {
+        if self.daily_status.played || !daily::start(GAME_ID) {
+            return;
         }
+        self.reset();
+        self.daily = true;
+        self.config = ReactionConfig {
+            input_latency_ms: self.config.input_latency_ms,
+            ..ReactionConfig::default()
+        };
+        // Drawn up front, false starts must not shift the waits of the attempts after them
+        let mut rng = daily::rng(GAME_ID);
+        let waits = self.config.min_wait_ms..=self.config.max_wait_ms;
+        self.daily_waits = (0..self.config.attempts).map(|_| rng.random_range(waits.clone())).collect();
+        self.start_waiting();
     }
 
     fn start_waiting(&mut self) {
         self.state = GameState::Waiting;
-        let mut rng = rand::rng();
-        let millis = rng.random_range(1800..4000);
-        self.wait_until = Some(Instant::now() + Duration::from_millis(millis));
+        let millis = match self.daily_waits.get(self.done as usize) {
+            Some(&millis) => millis,
+            None => self.rng.random_range(self.config.min_wait_ms..=self.config.max_wait_ms),
+        };
+        self.wait_left = Some(Duration::from_millis(millis));
         self.start_time = None;
     }
+
+    fn mode_id(&self) -> &'static str {
+        if self.config.is_standard() { MODE_ID } else { CUSTOM_MODE_ID }
+    }
+
+    /// Keeps the measured latency with the saved settings, not the ones a daily run replaced.
+    fn save_latency(&mut self, input_ms: u32) {
+        let mut saved = Self::load_config();
+        saved.input_latency_ms = input_ms;
+        self.config.input_latency_ms = input_ms;
+        // A replay of a calibration leaves this machine's latency alone
+        if !replay::is_playing() {
+            let _ = storage::save(CONFIG_FILE, &saved);
+        }
+    }
+
+    /// The score is the average reaction time in milliseconds.
+    fn session_record(&self, average_ms: u32) -> SessionRecord {
+        let mode = if self.daily { daily::MODE_ID } else { self.mode_id() };
+        SessionRecord::new(GAME_ID, mode, average_ms)
+            .with_metric("attempts", self.done as f64)
+            .with_metric("anticipated", self.flagged.len() as f64)
+            .with_metric("input_latency_ms", self.latency.input_ms as f64)
+            .with_trials(self.trials.clone())
+    }
+
+    /// Whether giving up now would lose anything worth asking about.
+    fn has_progress(&self) -> bool {
+        self.is_running() && self.done > 0
+    }
+
+    /// Keeps a trace of a run given up on, averaged over the attempts made so far.
+    fn record_aborted(&self) {
+        if self.has_progress() {
+            let average = self.reaction_history.iter().sum::<u32>() / self.done;
+            let _ = history::record(&self.session_record(average).aborted());
+        }
+    }
+
+    /// Drops the attempts made so far.
+    fn reset(&mut self) {
+        self.confirm = None;
+        self.done = 0;
+        self.reaction_history.clear();
+        self.flagged.clear();
+        self.trials.clear();
+        self.start_time = None;
+        self.wait_left = None;
+        self.calibration = None;
+        // Tells the other player, see `Connection::drop`
+        self.duel = None;
+
+        if self.daily {
+            // Back to the player's own settings, and waits nobody can predict
+            self.daily = false;
+            self.daily_waits.clear();
+            self.config = Self::load_config();
+            self.daily_status = DailyStatus::load(GAME_ID, self.ranking());
+        }
+    }
+
+    fn start_calibration(&mut self) {
+        self.state = GameState::Calibrating;
+        self.calibration = Some(Calibration::new(replay::now()));
+    }
+
+    /// Edits the host address of a duel, Enter hosts when it is left empty.
+    fn handle_address(&mut self, key_event: KeyEvent) {
+        match key_event.code {
+            KeyCode::Enter => {
+                let name = profile::current().map_or_else(|| "Player".to_string(), |profile| profile.name);
+                let address = self.address.trim().to_string();
+                let connection = if address.is_empty() {
+                    Connection::host(name)
+                } else {
+                    Connection::join(address, name)
+                };
+
+                self.reset();
+                self.duel = Some(Duel {
+                    connection,
+                    opponent: String::new(),
+                    round: 0,
+                    go_at: None,
+                    press: None,
+                    other_press: None,
+                    wins: 0,
+                    losses: 0,
+                    last_round: None,
+                    ended: None,
+                });
+                self.state = GameState::DuelConnecting;
+            }
+            KeyCode::Esc => self.state = GameState::Title,
+            KeyCode::Backspace => {
+                self.address.pop();
+            }
+            KeyCode::Char(c) if !key_event.modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) => {
+                self.address.push(c);
+            }
+            _ => {}
+        }
+    }
+
+    /// Handles what came from the other player, then the clock of the current round.
+    fn update_duel(&mut self) {
+        while let Some(event) = self.duel.as_mut().and_then(|duel| duel.connection.poll()) {
+            self.on_duel_event(event);
+        }
+
+        let now = replay::now();
+        let Some(duel) = self.duel.as_ref() else {
+            return;
+        };
+        let Some(go_at) = duel.go_at else {
+            return;
+        };
+        match self.state {
+            GameState::DuelWaiting if now >= go_at => {
+                self.state = GameState::DuelActive;
+                // The timer starts once the green frame has been drawn, see `on_frame_drawn`
+                self.start_time = None;
+            }
+            GameState::DuelActive if now >= go_at + DUEL_ROUND_TIMEOUT => self.duel_press(Press::Missed, now),
+            _ => {}
+        }
+    }
+
+    fn on_duel_event(&mut self, event: Event) {
+        let Some(duel) = self.duel.as_mut() else {
+            return;
+        };
+
+        match event {
+            Event::Connected { opponent } => {
+                duel.opponent = opponent;
+                match duel.connection.role() {
+                    Role::Host => self.start_round(),
+                    // Red until the host says when the first round turns green
+                    Role::Guest => self.state = GameState::DuelWaiting,
+                }
+            }
+            Event::Received(Message::Round { number, go_at_us }) => {
+                duel.round = number;
+                duel.go_at = Some(duel.connection.local_instant(go_at_us));
+                duel.press = None;
+                self.state = GameState::DuelWaiting;
+            }
+            Event::Received(Message::Press { round, press }) if round == duel.round => {
+                duel.other_press = Some(press);
+                self.judge_round();
+            }
+            Event::Received(Message::Outcome { round, host, guest, host_wins, guest_wins }) => {
+                // Seen from the guest's side
+                duel.wins = guest_wins;
+                duel.losses = host_wins;
+                let winner = duel::judge(guest, host);
+                duel.last_round = Some(describe_round(round, guest.press, host.press, &duel.opponent, winner));
+                self.end_round();
+            }
+            Event::Received(_) => {}
+            Event::Closed(reason) => {
+                // Once the duel is decided, the other player leaving changes nothing
+                if duel.wins < DUEL_WINS && duel.losses < DUEL_WINS {
+                    duel.ended = Some(reason);
+                    duel.go_at = None;
+                    self.state = GameState::DuelOver;
+                }
+            }
+        }
+    }
+
+    /// Host only: draws the next random wait and tells the other player when green comes.
+    fn start_round(&mut self) {
+        let millis = self.rng.random_range(self.config.min_wait_ms..=self.config.max_wait_ms);
+        let Some(duel) = self.duel.as_mut() else {
+            return;
+        };
+
+        let wait = DUEL_BREAK + Duration::from_millis(millis);
+        let go_at_us = duel.connection.host_clock_us() + wait.as_micros() as u64;
+        duel.round += 1;
+        duel.go_at = Some(duel.connection.local_instant(go_at_us));
+        duel.press = None;
+        duel.other_press = None;
+        self.state = GameState::DuelWaiting;
+
+        let round = Message::Round {
+            number: duel.round,
+            go_at_us,
+        };
+        if let Err(err) = duel.connection.send(&round) {
+            self.on_duel_event(Event::Closed(format!("Connection lost: {err}")));
+        }
+    }
+
+    /// Our own press of the round, judged on the host or sent to it.
+    fn duel_press(&mut self, press: Press, at: Instant) {
+        let Some(duel) = self.duel.as_mut() else {
+            return;
+        };
+        // Nothing to press for before the host started a round
+        if duel.press.is_some() || duel.go_at.is_none() {
+            return;
+        }
+
+        let timed = Timed {
+            press,
+            at_us: duel.connection.host_time_us(at),
+        };
+        duel.press = Some(timed);
+        self.start_time = None;
+        self.state = GameState::DuelPressed(press);
+        match duel.connection.role() {
+            Role::Host => self.judge_round(),
+            Role::Guest => {
+                let message = Message::Press {
+                    round: duel.round,
+                    press: timed,
+                };
+                if let Err(err) = duel.connection.send(&message) {
+                    self.on_duel_event(Event::Closed(format!("Connection lost: {err}")));
+                }
+            }
+        }
+    }
+
+    /// Host only: once both pressed, scores the round and tells the other player.
+    fn judge_round(&mut self) {
+        let Some(duel) = self.duel.as_mut() else {
+            return;
+        };
+        let (Some(host), Some(guest)) = (duel.press, duel.other_press) else {
+            return;
+        };
+
+        let winner = duel::judge(host, guest);
+        match winner {
+            Some(Role::Host) => duel.wins += 1,
+            Some(Role::Guest) => duel.losses += 1,
+            None => {}
+        }
+        duel.last_round = Some(describe_round(duel.round, host.press, guest.press, &duel.opponent, winner));
+
+        let outcome = Message::Outcome {
+            round: duel.round,
+            host,
+            guest,
+            host_wins: duel.wins,
+            guest_wins: duel.losses,
+        };
+        if let Err(err) = duel.connection.send(&outcome) {
+            self.on_duel_event(Event::Closed(format!("Connection lost: {err}")));
+            return;
+        }
+        self.end_round();
+    }
+
+    /// Ends the duel when someone won enough rounds, otherwise the host starts the next.
+    fn end_round(&mut self) {
+        let Some(duel) = self.duel.as_ref() else {
+            return;
+        };
+
+        if duel.wins >= DUEL_WINS || duel.losses >= DUEL_WINS {
+            if !self.reaction_history.is_empty() {
+                // Losing a result is not worth interrupting the game for
+                let _ = history::record(&self.duel_record(duel));
+            }
+            self.state = GameState::DuelOver;
+        } else if duel.connection.role() == Role::Host {
+            self.start_round();
+        }
+    }
+
+    /// The score is the average of the valid presses of the duel.
+    fn duel_record(&self, duel: &Duel) -> SessionRecord {
+        let average = self.reaction_history.iter().sum::<u32>() / self.reaction_history.len().max(1) as u32;
+        SessionRecord::new(GAME_ID, DUEL_MODE_ID, average)
+            .with_metric("rounds_won", duel.wins as f64)
+            .with_metric("rounds_lost", duel.losses as f64)
+            .with_metric("won", if duel.wins >= DUEL_WINS { 1.0 } else { 0.0 })
+            .with_metric("false_starts", self.trials.iter().filter(|t| t.correct == Some(false)).count() as f64)
+            .with_metric("input_latency_ms", self.latency.input_ms as f64)
+            .with_trials(self.trials.clone())
+    }
+
+    /// Score of the duel and how the last round went, under a headline.
+    fn duel_lines(&self, headline: &str) -> Vec<Line<'static>> {
+        let mut lines = vec![line_with_color(headline.to_string(), Color::White)];
+        if let Some(duel) = &self.duel {
+            lines.push(line_with_color(
+                format!("You {} – {} {} • first to {DUEL_WINS}", duel.wins, duel.losses, duel.opponent),
+                Color::White,
+            ));
+            if let Some(last) = &duel.last_round {
+                lines.push(line_with_color(last.clone(), Color::White));
+            }
+        }
+        lines
+    }
+}
+
+/// One line about a duel round, from our side.
+fn describe_round(round: u32, ours: Press, theirs: Press, opponent: &str, winner: Option<Role>) -> String {
+    let press = |press: Press| match press {
+        Press::Reaction(ms) => format!("{ms} ms"),
+        Press::FalseStart => "false start".to_string(),
+        Press::Missed => "no press".to_string(),
+    };
+    // `winner` is judged with us in the host's seat
+    let result = match winner {
+        Some(Role::Host) => "you win".to_string(),
+        Some(Role::Guest) => format!("{opponent} wins"),
+        None => "nobody scores".to_string(),
+    };
+    format!("Round {round}: you {}, {opponent} {} — {result}", press(ours), press(theirs))
+}
+
+impl Latency {
+    fn render_ms(&self) -> u32 {
+        if self.render_frames == 0 {
+            return 0;
+        }
+        (self.render_total / self.render_frames).as_millis() as u32
+    }
+}
+
+impl Calibration {
+    fn new(now: Instant) -> Self {
+        Self {
+            next_beat: now + BEAT_INTERVAL,
+            flash_until: None,
+            beats: 0,
+            beat_drawn: None,
+            last_tapped: 0,
+            offsets: Vec::new(),
+        }
+    }
+
+    fn update(&mut self, now: Instant) {
+        if let Some(until) = self.flash_until {
+            if now >= until {
+                self.flash_until = None;
+            }
+        }
+
+        if self.beats < BEAT_COUNT && now >= self.next_beat {
+            self.beats += 1;
+            self.flash_until = Some(now + BEAT_FLASH);
+            self.next_beat += BEAT_INTERVAL;
+            self.beat_drawn = None;
+        }
+    }
+
+    fn flash_drawn(&mut self, now: Instant) {
+        if self.flash_until.is_some() && self.beat_drawn.is_none() {
+            self.beat_drawn = Some(now);
+        }
+    }
+
+    fn tap(&mut self, now: Instant) {
+        let Some(drawn) = self.beat_drawn else {
+            return;
+        };
+
+        let mut offset = now.duration_since(drawn).as_millis() as i64;
+        let mut beat = self.beats;
+        // Closer to the upcoming flash than to the last one: an early tap for the next beat
+        if offset > BEAT_INTERVAL.as_millis() as i64 / 2 && self.beats < BEAT_COUNT {
+            offset -= BEAT_INTERVAL.as_millis() as i64;
+            beat += 1;
+        }
+
+        if beat == self.last_tapped {
+            return;
+        }
+        self.last_tapped = beat;
+
+        if beat > WARMUP_BEATS {
+            self.offsets.push(offset);
+        }
+    }
+
+    fn next_deadline(&self) -> Instant {
+        match self.flash_until {
+            Some(until) => until.min(self.next_beat),
+            None => self.next_beat,
+        }
+    }
+
+    fn is_finished(&self) -> bool {
+        self.beats >= BEAT_COUNT && self.flash_until.is_none() && self.next_beat <= replay::now()
+    }
+
+    /// Median tap offset, `None` when too few taps were in time with the beat.
+    fn latency_ms(&self) -> Option<u32> {
+        let mut offsets = self.offsets.clone();
+        if offsets.len() < (BEAT_COUNT - WARMUP_BEATS) as usize / 2 {
+            return None;
+        }
+        offsets.sort_unstable();
+        let median = offsets[offsets.len() / 2];
+
+        // Players tend to tap slightly ahead of a beat, so negative offsets mean no measurable latency
+        Some(median.clamp(0, MAX_INPUT_LATENCY_MS) as u32)
+    }
 }
@@ -0,0 +1,161 @@
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// What values a setting accepts.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SettingKind {
+    /// A whole number from `min` to `max`.
+    Int { min: i64, max: i64, step: i64 },
+    /// One of a fixed list of options, by label.
+    Choice(Vec<String>),
+    /// A length of time from `min` to `max`, edited in `step`s.
+    Duration { min: Duration, max: Duration, step: Duration },
+    Bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum SettingValue {
+    Int(i64),
+    /// Index of the selected option.
+    Choice(usize),
+    Duration(Duration),
+    Bool(bool),
+}
+
+/// One option of a game, as shown on its settings screen.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Setting {
+    /// Identifies the setting when the values come back to the game.
+    pub key: &'static str,
+    pub label: &'static str,
+    pub kind: SettingKind,
+    pub value: SettingValue,
+}
+
+impl Setting {
+    pub fn int(key: &'static str, label: &'static str, value: i64, min: i64, max: i64) -> Self {
+        Self {
+            key,
+            label,
+            kind: SettingKind::Int { min, max, step: 1 },
+            value: SettingValue::Int(value),
+        }
+    }
+
+    pub fn choice<T: Into<String>>(key: &'static str, label: &'static str, options: Vec<T>, selected: usize) -> Self {
+        Self {
+            key,
+            label,
+            kind: SettingKind::Choice(options.into_iter().map(Into::into).collect()),
+            value: SettingValue::Choice(selected),
+        }
+    }
+
+    pub fn duration(
+        key: &'static str,
+        label: &'static str,
+        value: Duration,
+        min: Duration,
+        max: Duration,
+        step: Duration,
+    ) -> Self {
+        Self {
+            key,
+            label,
+            kind: SettingKind::Duration { min, max, step },
+            value: SettingValue::Duration(value),
+        }
+    }
+
+    pub fn bool(key: &'static str, label: &'static str, value: bool) -> Self {
+        Self {
+            key,
+            label,
+            kind: SettingKind::Bool,
+            value: SettingValue::Bool(value),
+        }
+    }
+
+    /// Moves the value one step, staying within the bounds. Options wrap around.
+    pub fn step(&mut self, forward: bool) {
+        self.value = match (&self.kind, self.value) {
+            (SettingKind::Int { min, max, step }, SettingValue::Int(value)) => {
+                let next = if forward { value + step } else { value - step };
+                SettingValue::Int(next.clamp(*min, *max))
+            }
+            (SettingKind::Choice(options), SettingValue::Choice(index)) if !options.is_empty() => {
+                let len = options.len();
+                let next = if forward { index + 1 } else { index + len - 1 };
+                SettingValue::Choice(next % len)
+            }
+            (SettingKind::Duration { min, max, step }, SettingValue::Duration(value)) => {
+                let next = if forward { value + *step } else { value.saturating_sub(*step) };
+                SettingValue::Duration(next.clamp(*min, *max))
+            }
+            (_, SettingValue::Bool(value)) => SettingValue::Bool(!value),
+            (_, value) => value,
+        };
+    }
+
+    /// Whether the value fits the kind of setting and its bounds.
+    pub fn is_valid(&self) -> bool {
+        match (&self.kind, self.value) {
+            (SettingKind::Int { min, max, .. }, SettingValue::Int(value)) => (*min..=*max).contains(&value),
+            (SettingKind::Choice(options), SettingValue::Choice(index)) => index < options.len(),
+            (SettingKind::Duration { min, max, .. }, SettingValue::Duration(value)) => (*min..=*max).contains(&value),
+            (SettingKind::Bool, SettingValue::Bool(_)) => true,
+            _ => false,
+        }
+    }
+
+    /// The value as shown on the settings screen.
+    pub fn display(&self) -> String {
+        match (&self.kind, self.value) {
+            (SettingKind::Choice(options), SettingValue::Choice(index)) => {
+                options.get(index).cloned().unwrap_or_default()
+            }
+            (_, SettingValue::Int(value)) => value.to_string(),
+            (_, SettingValue::Duration(value)) => format!("{:.1} s", value.as_secs_f64()),
+            (_, SettingValue::Bool(value)) => if value { "On" } else { "Off" }.to_string(),
+            (_, SettingValue::Choice(index)) => index.to_string(),
+        }
+    }
+}
+
+/// Looks up the value of a setting by key, for games reading back an edited form.
+pub fn value(settings: &[Setting], key: &str) -> Option<SettingValue> {
+    settings
+        .iter()
+        .find(|setting| setting.key == key)
+        .map(|setting| setting.value)
+}
+
+impl SettingValue {
+    pub fn as_int(&self) -> Option<i64> {
+        match self {
+            SettingValue::Int(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn as_choice(&self) -> Option<usize> {
+        match self {
+            SettingValue::Choice(index) => Some(*index),
+            _ => None,
+        }
+    }
+
+    pub fn as_duration(&self) -> Option<Duration> {
+        match self {
+            SettingValue::Duration(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            SettingValue::Bool(value) => Some(*value),
+            _ => None,
+        }
+    }
+}
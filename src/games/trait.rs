@@ -1,26 +1,126 @@
-use crossterm::event;
+use crate::games::settings::Setting;
+use crate::history::Ranking;
+use crate::keymap::KeyMap;
 use crossterm::event::{Event, KeyEvent, KeyEventKind};
-use ratatui::Terminal;
-use ratatui::backend::CrosstermBackend;
-use std::io;
+use ratatui::Frame;
+use std::time::{Duration, Instant};
 
+/// Shelves of the menu.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Category {
+    Reflex,
+    Memory,
+    Puzzle,
+    Arcade,
+}
+
+impl Category {
+    /// In the order the menu lists them.
+    pub const ALL: [Category; 4] = [Category::Reflex, Category::Memory, Category::Puzzle, Category::Arcade];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Category::Reflex => "Reflex",
+            Category::Memory => "Memory",
+            Category::Puzzle => "Puzzle",
+            Category::Arcade => "Arcade",
+        }
+    }
+}
+
+/// A game driven by the `Engine`: it owns the terminal and the event pump, games only
+/// react to events, advance their timers and draw themselves.
 pub trait Game {
     fn name(&self) -> &str;
     fn description(&self) -> &str;
 
+    // Identifier the game's sessions are recorded under, must not change
+    fn id(&self) -> &str;
+
+    // Where the menu lists the game, anything that fits nowhere else is arcade
+    fn category(&self) -> Category {
+        Category::Arcade
+    }
+
+    // Whether a session's score is better high or low
+    fn ranking(&self) -> Ranking {
+        Ranking::HigherIsBetter
+    }
+
+    // What a score counts, shown next to it
+    fn score_unit(&self) -> &str {
+        "points"
+    }
+
+    // Controls and rules, shown by the help screen
+    fn help(&self) -> &str;
+
+    // Options offered by the settings screen with their current values, none by default
+    fn settings(&self) -> Vec<Setting> {
+        Vec::new()
+    }
+
+    // Takes the values edited on the settings screen and keeps them for the next runs.
+    // Each value is within its bounds, values that don't work together are refused with
+    // the reason, and nothing changes then
+    fn apply_settings(&mut self, _settings: &[Setting]) -> Result<(), String> {
+        Ok(())
+    }
+
+    // Advances timers by `dt`, the time since the previous update
+    fn update(&mut self, _dt: Duration) {}
+
+    fn render(&self, frame: &mut Frame);
+
+    // Called before the engine runs the game, with the keys its hints should name
+    fn set_keys(&mut self, _keys: &KeyMap) {}
+
     // Default just reading keys
-    fn handle_events(&mut self) -> io::Result<()> {
-        // Just handling key presses
-        match event::read()? {
-            Event::Key(key_event) if key_event.kind == KeyEventKind::Press => {
-                self.handle_input(key_event)
+    fn on_event(&mut self, event: Event, keys: &KeyMap) {
+        // it's important to check that the event is a key press event as
+        // crossterm also emits key release and repeat events on Windows.
+        if let Event::Key(key_event) = event {
+            if key_event.kind == KeyEventKind::Press {
+                self.handle_input(key_event, keys)
             }
-            _ => {}
-        };
-        Ok(())
+        }
+    }
+
+    // `keys` tells which actions the key triggers, games only match literal keys for
+    // their own shortcuts
+    fn handle_input(&mut self, key_event: KeyEvent, keys: &KeyMap);
+
+    // Whether the player is done with the game and wants to go back to the menu
+    fn wants_exit(&self) -> bool;
+
+    // How long the engine can wait for input before the next update, `None` when nothing
+    // changes until the player presses a key
+    fn wake_in(&self) -> Option<Duration> {
+        None
     }
-    fn handle_input(&mut self, key_event: KeyEvent);
 
-    // Games can return to choose between terminating the whole game or just going back to menu
-    fn run(&mut self, terminal: &mut Terminal<CrosstermBackend<io::Stdout>>) -> io::Result<()>;
+    // Called right after a frame has been flushed to the terminal, with how long drawing it took
+    fn on_frame_drawn(&mut self, _at: Instant, _draw_time: Duration) {}
+
+    // Whether a run is in progress, Esc pauses it instead of reaching the game
+    fn is_running(&self) -> bool {
+        false
+    }
+
+    // Called when the pause overlay opens, for clocks that don't run on `update`
+    fn on_pause(&mut self) {}
+
+    // Called when the pause overlay is closed, timers have not moved in the meantime
+    fn on_resume(&mut self) {}
+
+    // Whether the pause menu offers to restart the run, a daily challenge is played once
+    fn can_restart(&self) -> bool {
+        true
+    }
+
+    // Throws the current run away and starts a new one with the same settings
+    fn restart(&mut self);
+
+    // Called when leaving back to the menu, so the game starts fresh next time
+    fn on_exit(&mut self);
 }
@@ -1,8 +1,137 @@
+use crate::keymap::{Action, KeyMap};
+use crate::reference::Distribution;
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::layout::{Alignment, Constraint, Direction, Flex, Layout, Rect};
 use ratatui::prelude::{Color, Line, Modifier, Span, Style};
+use ratatui::symbols::Marker;
+use ratatui::widgets::{Axis, Block, Borders, Chart, Clear, Dataset, GraphType, Paragraph, Wrap};
+use ratatui::Frame;
 
 pub fn line_with_color<T: Into<String>>(text: T, color: Color) -> Line<'static> {
     Line::from(Span::styled(
         text.into(),
         Style::default().fg(color).add_modifier(Modifier::BOLD),
     ))
-}
\ No newline at end of file
+}
+
+/// A box of at most `width` × `height` in the middle of `area`.
+pub fn centered_rect(area: Rect, width: u16, height: u16) -> Rect {
+    let [row] = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(height)])
+        .flex(Flex::Center)
+        .areas(area);
+    let [cell] = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Length(width)])
+        .flex(Flex::Center)
+        .areas(row);
+    cell
+}
+
+/// How the scores of the reference population spread, with the player's score marked.
+///
+/// Drawn in black over the end screens' background, the marker in red.
+pub fn render_distribution(frame: &mut Frame, area: Rect, distribution: &Distribution, score: u32) {
+    let curve = distribution.curve();
+    let peak = curve.iter().map(|(_, weight)| *weight).fold(0.0, f64::max);
+    let (low, high) = distribution.bounds();
+    let (low, high) = (low.min(score as f64), high.max(score as f64 + 1.0));
+    let marker = [(score as f64, 0.0), (score as f64, peak)];
+
+    let datasets = vec![
+        Dataset::default()
+            .marker(Marker::Braille)
+            .graph_type(GraphType::Line)
+            .style(Style::default().fg(Color::Black))
+            .data(&curve),
+        Dataset::default()
+            .marker(Marker::Braille)
+            .graph_type(GraphType::Line)
+            .style(Style::default().fg(Color::Red))
+            .data(&marker),
+    ];
+    let chart = Chart::new(datasets)
+        .x_axis(
+            Axis::default()
+                .style(Style::default().fg(Color::Black))
+                .bounds([low, high])
+                .labels([format!("{low:.0}"), format!("{high:.0}")]),
+        )
+        .y_axis(Axis::default().bounds([0.0, peak * 1.1]));
+
+    frame.render_widget(chart, centered_rect(area, 60, 8));
+}
+
+/// A yes/no question drawn over the game, used before throwing progress away.
+///
+/// The game keeps it while it is open, hands it every key and acts on the answer.
+/// "No" is selected first, so mashing Enter never confirms by accident.
+pub struct ConfirmDialog {
+    message: String,
+    yes_selected: bool,
+}
+
+impl ConfirmDialog {
+    pub fn new<T: Into<String>>(message: T) -> Self {
+        Self {
+            message: message.into(),
+            yes_selected: false,
+        }
+    }
+
+    /// Returns the answer once the player made one, `None` while the question is still open.
+    pub fn handle_key(&mut self, key_event: KeyEvent, keys: &KeyMap) -> Option<bool> {
+        match key_event.code {
+            KeyCode::Char('y') => return Some(true),
+            KeyCode::Char('n') => return Some(false),
+            KeyCode::Tab => {
+                self.yes_selected = !self.yes_selected;
+                return None;
+            }
+            _ => {}
+        }
+
+        match keys.action(&key_event, &[Action::Confirm, Action::Back, Action::Left, Action::Right]) {
+            Some(Action::Confirm) => Some(self.yes_selected),
+            Some(Action::Back) => Some(false),
+            Some(_) => {
+                self.yes_selected = !self.yes_selected;
+                None
+            }
+            None => None,
+        }
+    }
+
+    pub fn render(&self, frame: &mut Frame) {
+        let selected = Style::default()
+            .bg(Color::Blue)
+            .fg(Color::White)
+            .add_modifier(Modifier::BOLD);
+        let button = |label: &'static str, active: bool| {
+            Span::styled(label, if active { selected } else { Style::default() })
+        };
+
+        let lines = vec![
+            Line::from(self.message.clone()),
+            Line::from(""),
+            Line::from(vec![
+                button("  Yes  ", self.yes_selected),
+                Span::raw("    "),
+                button("  No  ", !self.yes_selected),
+            ]),
+            Line::from(""),
+            Line::from("y / n, or pick one and confirm"),
+        ];
+
+        let area = centered_rect(frame.area(), 50, lines.len() as u16 + 4);
+        let dialog = Paragraph::new(lines)
+            .style(Style::default().fg(Color::White).bg(Color::Black))
+            .alignment(Alignment::Center)
+            .wrap(Wrap { trim: true })
+            .block(Block::default().borders(Borders::ALL).title(" Confirm "));
+
+        frame.render_widget(Clear, area);
+        frame.render_widget(dialog, area);
+    }
+}
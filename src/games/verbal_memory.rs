@@ -1,39 +1,165 @@
-use crate::games::Game;
-use crate::games::utils::line_with_color;
+use crate::daily::{self, DailyStatus};
+use crate::games::hot_seat::{HotSeat, NameEntry};
+use crate::games::settings::{self, Setting};
+use crate::games::{Category, Game};
+use crate::games::utils::{line_with_color, render_distribution, ConfirmDialog};
+use crate::games::word_list::{Language, WordSource};
+use crate::games::word_selection::{NextWord, Progressive, SeenWord, SelectionContext, WordSelection};
+use crate::history::{self, Ranking, SessionRecord, Trial};
+use crate::keymap::{Action, KeyMap};
+use crate::profile;
+use crate::reference::{self, Distribution};
+use crate::replay::{self, GameRng};
+use crate::storage;
 use crossterm::event::{KeyCode, KeyEvent};
-use rand::Rng;
-use ratatui::backend::CrosstermBackend;
 use ratatui::layout::{Alignment, Constraint, Direction, Layout, Rect};
 use ratatui::style::{Color, Modifier, Style};
-use ratatui::widgets::{Block, Paragraph};
-use ratatui::{Frame, Terminal};
-use std::collections::HashSet;
-use std::io;
-use std::io::{Stdout};
-use rand::prelude::{IndexedRandom, IteratorRandom};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Gauge, Paragraph};
+use ratatui::Frame;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use rand::prelude::IteratorRandom;
+
+const GAME_ID: &str = "verbal_memory";
+const CONFIG_FILE: &str = "verbal_memory.json";
+/// How many runs the title screen lists for the selected mode.
+const LEADERBOARD_SIZE: usize = 5;
+/// Answers in a pass-and-play turn before the keyboard goes to the next player.
+const TURN_WORDS: u32 = 5;
 
 enum GameState {
     Title,   // Initial screen
     Showing, // Showing words to player
     End,     // Game over
+    Players,   // Typing the names of a pass-and-play game
+    Turn,      // Between two pass-and-play turns
+    Standings, // Every pass-and-play player is out
 }
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum Choice {
-    SEEN,
-    NEW,
+    Seen,
+    New,
+}
+
+/// Ways to play a run, each mode has its own leaderboard.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+enum Mode {
+    Classic,     // Three lives
+    SuddenDeath, // The first mistake ends the run
+    Timed,       // As many words as possible before the clock runs out
+    Zen,         // No lives, play until you quit and see how accurate you were
+}
+
+/// Verbal memory preferences, kept in the data directory between runs.
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+struct VerbalMemoryConfig {
+    /// Word list picked last time on the title screen.
+    source: WordSource,
+    /// Own word list to offer next to the bundled ones, one word per line.
+    word_file: Option<PathBuf>,
+    /// Mode picked last time on the title screen.
+    mode: Mode,
+    /// Seen and New answer right away instead of moving the selection.
+    one_key: bool,
+}
+
+impl Default for VerbalMemoryConfig {
+    fn default() -> Self {
+        Self {
+            source: WordSource::Bundled(Language::English),
+            word_file: None,
+            mode: Mode::Classic,
+            one_key: false,
+        }
+    }
 }
 
 pub struct VerbalMemory {
     state: GameState,
+    sources: Vec<WordSource>,
+    source_index: usize,
+    load_error: Option<String>,
     words: Vec<String>,
-    words_seen: HashSet<String>,
+    words_seen: Vec<SeenWord>,
     word_shown: Option<String>,
-    lives: u32,
-    score: u32,
-    choice: Choice,
+    turn: u32,
+    selection: Box<dyn WordSelection>,
+    mode: Mode,
+    lives: Option<u32>,                 // Lives left, `None` when mistakes don't end the run
+    score: u32,                         // Correct answers
+    answered: u32,                      // Total answers, for the accuracy
+    elapsed: Duration,                  // Time spent answering in the current run
+    leaderboard: Vec<SessionRecord>,    // Best runs of the selected mode
+    last_record: Option<SessionRecord>, // The run that just ended
+    choice: Option<Choice>,             // Answer picked for the current word, if any
+    one_key: bool,                      // Seen and New answer without confirming
+    keys: KeyMap,                       // Keys named by the hints
+    word_started: Option<Instant>,      // When the clock of the current word last started
+    word_time: Duration,                // Time spent on the current word before a pause
+    response_times: Vec<Duration>,      // How long each answer took in the current run
+    trials: Vec<Trial>,                 // Every word of the current run with its answer
+    confirm: Option<ConfirmDialog>,     // Asks before a run in progress is thrown away
+    reference: Option<Distribution>,    // How other people score in the mode of the last run
+    names: Option<NameEntry>,           // Names typed for a pass-and-play game, before it starts
+    hot_seat: Option<HotSeat>,          // Pass-and-play players, sharing the words seen
+    turn_answers: u32,                  // Answers given in the current pass-and-play turn
+    rng: GameRng,                        // Draws the words, seeded from the date for the daily challenge
+    daily: bool,                        // Whether the run is today's challenge
+    daily_status: DailyStatus,          // Today's challenge, streak and best days
     quit: bool,
 }
 
+impl Mode {
+    const ALL: [Mode; 4] = [Mode::Classic, Mode::SuddenDeath, Mode::Timed, Mode::Zen];
+
+    fn name(&self) -> &'static str {
+        match self {
+            Mode::Classic => "Classic",
+            Mode::SuddenDeath => "Sudden Death",
+            Mode::Timed => "Timed",
+            Mode::Zen => "Zen",
+        }
+    }
+
+    /// Stored with the results, so it must not change.
+    fn id(&self) -> &'static str {
+        match self {
+            Mode::Classic => "classic",
+            Mode::SuddenDeath => "sudden_death",
+            Mode::Timed => "timed",
+            Mode::Zen => "zen",
+        }
+    }
+
+    fn rules(&self) -> &'static str {
+        match self {
+            Mode::Classic => "3 lives, a mistake costs one",
+            Mode::SuddenDeath => "1 life, the first mistake ends the run",
+            Mode::Timed => "As many words as you can in 60 seconds",
+            Mode::Zen => "No lives, quit when you are done",
+        }
+    }
+
+    /// Lives at the start of a run, `None` when mistakes never end it.
+    fn lives(&self) -> Option<u32> {
+        match self {
+            Mode::Classic => Some(3),
+            Mode::SuddenDeath => Some(1),
+            Mode::Timed | Mode::Zen => None,
+        }
+    }
+
+    fn time_limit(&self) -> Option<Duration> {
+        match self {
+            Mode::Timed => Some(Duration::from_secs(60)),
+            _ => None,
+        }
+    }
+}
+
 impl Game for VerbalMemory {
     fn name(&self) -> &str {
         "🧠📝 Verbal Memory"
@@ -43,9 +169,122 @@ impl Game for VerbalMemory {
         "Keep as many words in short term memory as possible"
     }
 
-    fn handle_input(&mut self, key_event: KeyEvent) {
-        match key_event.code {
-            KeyCode::Char('q') | KeyCode::Esc => {
+    fn id(&self) -> &str {
+        GAME_ID
+    }
+
+    fn category(&self) -> Category {
+        Category::Memory
+    }
+
+    fn score_unit(&self) -> &str {
+        "words"
+    }
+
+    fn help(&self) -> &str {
+        "Words are shown one at a time. Say whether you have seen each one earlier in the run.\n\
+         Classic: 3 lives • Sudden death: 1 life • Timed: 60 seconds • Zen: no lives\n\
+         \n\
+         Title screen: left/right word list, up/down mode, confirm to start\n\
+         In game: Seen or New to pick an answer, confirm to answer\n\
+         Tab (title screen): answer with a single key, Seen or New answers right away\n\
+         t (title screen): today's challenge, the same English words for everyone with\n\
+         Classic rules, one attempt a day\n\
+         p (title screen): pass and play, 2 to 4 players take turns of 5 words on the same\n\
+         stream of words, each with their own lives. Classic and Sudden Death only\n\
+         Back: end a Zen run, otherwise back to the title screen"
+    }
+
+    fn settings(&self) -> Vec<Setting> {
+        vec![
+            Setting::choice(
+                "mode",
+                "Mode",
+                Mode::ALL.iter().map(Mode::name).collect(),
+                Mode::ALL.iter().position(|mode| *mode == self.mode).unwrap_or(0),
+            ),
+            Setting::choice(
+                "word_list",
+                "Word list",
+                self.sources.iter().map(WordSource::label).collect(),
+                self.source_index,
+            ),
+            Setting::bool("one_key", "One-key answers", self.one_key),
+        ]
+    }
+
+    fn apply_settings(&mut self, values: &[Setting]) -> Result<(), String> {
+        let mut config: VerbalMemoryConfig = storage::load(CONFIG_FILE);
+
+        if let Some(index) = settings::value(values, "word_list").and_then(|v| v.as_choice()) {
+            // A list that can't be played is refused here rather than on the title screen
+            let source = &self.sources[index];
+            source
+                .load()
+                .map_err(|err| format!("Can't use {}: {err}", source.label()))?;
+            self.source_index = index;
+            self.words.clear();
+            self.load_error = None;
+            config.source = source.clone();
+        }
+        if let Some(index) = settings::value(values, "mode").and_then(|v| v.as_choice()) {
+            self.mode = Mode::ALL[index];
+            self.lives = self.mode.lives();
+            self.refresh_leaderboard();
+            config.mode = self.mode;
+        }
+        if let Some(one_key) = settings::value(values, "one_key").and_then(|v| v.as_bool()) {
+            self.one_key = one_key;
+            config.one_key = one_key;
+        }
+
+        // Forgetting the choice is not worth interrupting the game for
+        let _ = storage::save(CONFIG_FILE, &config);
+        Ok(())
+    }
+
+    fn handle_input(&mut self, key_event: KeyEvent, keys: &KeyMap) {
+        // Taken first so the time spent matching below is not counted
+        let now = replay::now();
+
+        if let Some(names) = self.names.as_mut() {
+            match names.handle_key(key_event) {
+                Some(true) => self.start_hot_seat(),
+                Some(false) => self.reset_game(),
+                None => {}
+            }
+            if self.names.is_none() && self.hot_seat.is_none() {
+                self.state = GameState::Title;
+            }
+            return;
+        }
+
+        if let Some(confirm) = self.confirm.as_mut() {
+            match confirm.handle_key(key_event, keys) {
+                Some(true) => {
+                    self.confirm = None;
+                    self.abandon_run();
+                }
+                Some(false) => {
+                    self.confirm = None;
+                    self.on_resume();
+                }
+                None => {}
+            }
+            return;
+        }
+
+        if keys.is(Action::Back, &key_event) {
+            // Zen runs only end when the player decides to, so quitting finishes them
+            if self.mode == Mode::Zen && matches!(self.state, GameState::Showing) {
+                self.finish_run();
+            } else if self.has_progress() {
+                self.on_pause();
+                self.confirm = Some(ConfirmDialog::new(match self.hot_seat {
+                    Some(_) => "Give up this game? The standings so far will be lost.".to_string(),
+                    None => format!("Give up this run? {} correct so far will be lost.", self.score),
+                }));
+            } else {
                 self.reset_game();
 
                 match self.state {
@@ -53,106 +292,227 @@ impl Game for VerbalMemory {
                     _ => self.state = GameState::Title,
                 }
             }
-            _ => {}
+            return;
         }
 
         // Now handle state-specific actions.
         match self.state {
-            GameState::Title => match key_event.code {
-                KeyCode::Enter => {
-                    self.assign_random_word_based_on_progress();
-                    self.state = GameState::Showing;
-                }
-                _ => {}
-            },
-            GameState::Showing => {
-                match key_event.code {
-                    // Seen
-                    KeyCode::Char('a') | KeyCode::Left => {
-                        self.choice = Choice::SEEN;
+            GameState::Title => {
+                let among = [Action::Left, Action::Right, Action::Up, Action::Down, Action::Confirm];
+                match keys.action(&key_event, &among) {
+                    Some(Action::Left) => self.select_source(false),
+                    Some(Action::Right) => self.select_source(true),
+                    Some(Action::Up) => self.select_mode(false),
+                    Some(Action::Down) => self.select_mode(true),
+                    Some(Action::Confirm) if self.init_words_vec() => {
+                        self.reset_game();
+                        self.assign_random_word_based_on_progress();
+                        self.state = GameState::Showing;
                     }
-                    // New
-                    KeyCode::Char('d') | KeyCode::Right => {
-                        self.choice = Choice::NEW;
+                    None if key_event.code == KeyCode::Tab => {
+                        self.one_key = !self.one_key;
+                        let mut config: VerbalMemoryConfig = storage::load(CONFIG_FILE);
+                        config.one_key = self.one_key;
+                        let _ = storage::save(CONFIG_FILE, &config);
                     }
-                    KeyCode::Enter => {
-                        // Default to false, so no points are reduced
-                        let is_seen = self
-                            .word_shown
-                            .as_ref()
-                            .map_or(false, |word| self.words_seen.contains(word));
-                        let is_new = !is_seen;
-
-                        // Adjust score and lives based on choice and correctness
-                        if self.choice == Choice::SEEN {
-                            if is_seen {
-                                self.score += 1;
-                            } else {
-                                self.lives -= 1;
-                            }
+                    None if key_event.code == KeyCode::Char('t') => self.start_daily(),
+                    None if key_event.code == KeyCode::Char('p') => {
+                        if self.mode.lives().is_some() {
+                            let current = profile::current().map(|profile| profile.name);
+                            self.names = Some(NameEntry::new(current));
+                            self.load_error = None;
+                            self.state = GameState::Players;
                         } else {
-                            if is_new {
-                                self.score += 1;
-                            } else {
-                                self.lives -= 1;
-                            }
-                            if let Some(word) = self.word_shown.as_ref() {
-                                self.words_seen.insert(word.clone());
-                            }
-                        }
-
-                        // Handle game over or progress
-                        if self.lives <= 0 {
-                            self.state = GameState::End;
-                        } else {
-                            self.assign_random_word_based_on_progress();
+                            self.load_error = Some("Pass and play needs lives, pick Classic or Sudden Death".to_string());
                         }
                     }
                     _ => {}
                 }
             }
-            GameState::End => match key_event.code {
-                KeyCode::Enter => {
+            GameState::Showing => {
+                let picked = match keys.action(&key_event, &[Action::Seen, Action::New, Action::Confirm]) {
+                    Some(Action::Seen) => Some(Choice::Seen),
+                    Some(Action::New) => Some(Choice::New),
+                    Some(_) => {
+                        if let Some(choice) = self.choice {
+                            self.answer(choice, now);
+                        }
+                        None
+                    }
+                    None => None,
+                };
+
+                if let Some(choice) = picked {
+                    if self.one_key {
+                        self.answer(choice, now);
+                    } else {
+                        self.choice = Some(choice);
+                    }
+                }
+            }
+            GameState::End | GameState::Standings => {
+                if keys.is(Action::Confirm, &key_event) {
                     self.state = GameState::Title;
                     self.reset_game();
                 }
-                _ => {}
-            },
+            }
+            GameState::Turn => {
+                if keys.is(Action::Confirm, &key_event) {
+                    self.start_turn();
+                }
+            }
+            // Names are typed in `NameEntry`, handled above
+            GameState::Players => {}
         }
     }
 
-    fn run(&mut self, terminal: &mut Terminal<CrosstermBackend<Stdout>>) -> io::Result<()> {
-        self.init_words_vec();
+    fn update(&mut self, dt: Duration) {
+        // The clock doesn't run while the player is asked whether to give up
+        if !matches!(self.state, GameState::Showing) || self.confirm.is_some() {
+            return;
+        }
+
+        self.elapsed += dt;
+        if self.is_out_of_time() {
+            self.finish_run();
+        }
+    }
 
-        while !self.quit {
-            terminal
-                .draw(|frame| match self.state {
-                    GameState::Title => self.render_title_screen(frame),
-                    GameState::Showing => self.render_on_game_screen(frame),
-                    GameState::End => self.render_game_over_screen(frame),
-                })
-                .expect("Error while rendering game");
+    fn render(&self, frame: &mut Frame) {
+        match self.state {
+            GameState::Title => self.render_title_screen(frame),
+            GameState::Showing => self.render_on_game_screen(frame),
+            GameState::End => self.render_game_over_screen(frame),
+            GameState::Players => {
+                if let Some(names) = &self.names {
+                    self.render_centered(frame, names.lines(Color::White));
+                }
+            }
+            GameState::Turn => {
+                if let Some(hot_seat) = &self.hot_seat {
+                    let detail = format!("{TURN_WORDS} words, seen or new for everyone at the table");
+                    self.render_centered(frame, hot_seat.turn_lines(&detail, Color::White));
+                }
+            }
+            GameState::Standings => {
+                if let Some(hot_seat) = &self.hot_seat {
+                    self.render_centered(frame, hot_seat.standings_lines(self.score_unit(), Color::White));
+                }
+            }
+        }
 
-            self.handle_events()?;
+        if let Some(confirm) = &self.confirm {
+            confirm.render(frame);
         }
+    }
+
+    fn wants_exit(&self) -> bool {
+        self.quit
+    }
+
+    // Keep the countdown moving while waiting for an answer
+    fn set_keys(&mut self, keys: &KeyMap) {
+        self.keys = keys.clone();
+    }
+
+    fn wake_in(&self) -> Option<Duration> {
+        let timed = self.mode.time_limit().is_some() && matches!(self.state, GameState::Showing);
+        timed.then_some(Duration::ZERO)
+    }
 
+    fn is_running(&self) -> bool {
+        matches!(self.state, GameState::Showing | GameState::Turn)
+    }
+
+    // The response time runs on the wall clock, bank what was spent so far
+    fn on_pause(&mut self) {
+        if let Some(started) = self.word_started.take() {
+            self.word_time += replay::now().duration_since(started);
+        }
+    }
+
+    fn on_resume(&mut self) {
+        if matches!(self.state, GameState::Showing) {
+            self.word_started = Some(replay::now());
+        }
+    }
+
+    fn can_restart(&self) -> bool {
+        !self.daily
+    }
+
+    fn restart(&mut self) {
+        self.record_aborted();
+        self.reset_game();
+        self.assign_random_word_based_on_progress();
+        self.state = GameState::Showing;
+    }
+
+    fn on_exit(&mut self) {
+        self.record_aborted();
         self.quit_game();
-        Ok(())
     }
 }
 
 impl VerbalMemory {
-    pub fn new() -> Self {
-        Self {
+    /// `word_file` is a word list given on the command line, it is picked over the saved choice.
+    pub fn new(word_file: Option<PathBuf>) -> Self {
+        let config: VerbalMemoryConfig = storage::load(CONFIG_FILE);
+
+        let mut sources: Vec<WordSource> =
+            Language::ALL.iter().copied().map(WordSource::Bundled).collect();
+        for path in config.word_file.into_iter().chain(word_file.clone()) {
+            let source = WordSource::File(path);
+            if !sources.contains(&source) {
+                sources.push(source);
+            }
+        }
+
+        let selected = word_file.map(WordSource::File).unwrap_or(config.source);
+        let source_index = match sources.iter().position(|source| *source == selected) {
+            Some(index) => index,
+            None => {
+                sources.push(selected);
+                sources.len() - 1
+            }
+        };
+
+        let mut game = Self {
             state: GameState::Title,
+            sources,
+            source_index,
+            load_error: None,
             words: Vec::new(),
-            words_seen: HashSet::new(),
+            words_seen: Vec::new(),
             word_shown: None,
-            lives: 3,
+            turn: 0,
+            selection: Box::new(Progressive::default()),
+            mode: config.mode,
+            lives: config.mode.lives(),
             score: 0,
-            choice: Choice::SEEN,
+            answered: 0,
+            elapsed: Duration::ZERO,
+            leaderboard: Vec::new(),
+            last_record: None,
+            choice: None,
+            one_key: config.one_key,
+            keys: KeyMap::default(),
+            word_started: None,
+            word_time: Duration::ZERO,
+            response_times: Vec::new(),
+            trials: Vec::new(),
+            confirm: None,
+            reference: None,
+            names: None,
+            hot_seat: None,
+            turn_answers: 0,
+            rng: GameRng::new(),
+            daily: false,
+            daily_status: DailyStatus::load(GAME_ID, Ranking::HigherIsBetter),
             quit: false,
-        }
+        };
+        game.refresh_leaderboard();
+        game
     }
 
     fn render_title_screen(&self, frame: &mut Frame) {
@@ -163,7 +523,67 @@ impl VerbalMemory {
                 "You will be shown words, one at a time. If you've seen a word during the test, click SEEN, If it's a new word, click NEW",
                 Color::White,
             ),
+            line_with_color("", Color::White),
+            line_with_color(
+                format!("Mode: ▲ {} ▼  ({})", self.mode.name(), self.mode.rules()),
+                Color::White,
+            ),
+            line_with_color(
+                format!("Words: ◀ {} ▶", self.sources[self.source_index].label()),
+                Color::White,
+            ),
+            line_with_color(
+                if self.one_key {
+                    format!(
+                        "Answers: one key, {} Seen • {} New (Tab to change)",
+                        self.keys.label(Action::Seen),
+                        self.keys.label(Action::New)
+                    )
+                } else {
+                    format!(
+                        "Answers: pick with {} and {}, then {} (Tab to change)",
+                        self.keys.label(Action::Left),
+                        self.keys.label(Action::Right),
+                        self.keys.label(Action::Confirm)
+                    )
+                },
+                Color::White,
+            ),
+            line_with_color(
+                "↑ ↓ to change the mode • ← → to change the word list • p for pass and play • Enter to start",
+                Color::White,
+            ),
         ];
+        let mut lines = match &self.load_error {
+            Some(err) => [lines, vec![line_with_color(err.clone(), Color::Red)]].concat(),
+            None => lines,
+        };
+        lines.push(line_with_color("", Color::White));
+        lines.extend(self.daily_status.lines(self.score_unit(), Color::White));
+
+        lines.push(line_with_color("", Color::White));
+        if self.leaderboard.is_empty() {
+            lines.push(line_with_color(
+                format!("No {} runs yet", self.mode.name()),
+                Color::White,
+            ));
+        } else {
+            lines.push(line_with_color(
+                format!("Best {} runs", self.mode.name()),
+                Color::White,
+            ));
+            for (i, record) in self.leaderboard.iter().enumerate() {
+                lines.push(line_with_color(
+                    format!(
+                        "{}. {} words  {}",
+                        i + 1,
+                        record.score,
+                        record.played_at.format("%Y-%m-%d")
+                    ),
+                    Color::White,
+                ));
+            }
+        }
 
         let size = frame.area();
 
@@ -203,12 +623,21 @@ impl VerbalMemory {
                 Constraint::Length(3), // Score + Lives
                 Constraint::Length(3), // Word
                 Constraint::Length(3), // Buttons
+                Constraint::Length(1), // Countdown
                 Constraint::Percentage(30),
             ])
             .split(size);
 
         // Score and Lives
-        let score_line = format!("Score: {}    Lives: {}", self.score, self.lives);
+        let score_line = match (self.lives, self.mode) {
+            (Some(lives), _) => format!("Score: {}    Lives: {}", self.score, lives),
+            (None, Mode::Zen) => format!("Score: {}    Accuracy: {}%", self.score, self.accuracy_percent()),
+            (None, _) => format!("Score: {}", self.score),
+        };
+        let score_line = match &self.hot_seat {
+            Some(hot_seat) => format!("{}    {score_line}", hot_seat.current().name),
+            None => score_line,
+        };
 
         let score_paragraph = Paragraph::new(score_line)
             .style(Style::default().fg(Color::White).bg(Color::Cyan))
@@ -242,16 +671,18 @@ impl VerbalMemory {
             ])
             .split(button_area);
 
+        let idle = Style::default().fg(Color::White).bg(Color::Cyan);
         let (seen_style, new_style) = match self.choice {
-            Choice::SEEN => (
+            None => (idle, idle),
+            Some(Choice::Seen) => (
                 Style::default()
                     .fg(Color::Cyan)
                     .bg(Color::Black)
                     .add_modifier(Modifier::BOLD),
-                Style::default().fg(Color::White).bg(Color::Cyan),
+                idle,
             ),
-            Choice::NEW => (
-                Style::default().fg(Color::White).bg(Color::Cyan),
+            Some(Choice::New) => (
+                idle,
                 Style::default()
                     .fg(Color::Cyan)
                     .bg(Color::Black)
@@ -268,15 +699,77 @@ impl VerbalMemory {
 
         frame.render_widget(seen, button_chunks[0]);
         frame.render_widget(new, button_chunks[2]);
+
+        // Countdown for timed runs
+        if let Some(limit) = self.mode.time_limit() {
+            let left = limit.saturating_sub(self.elapsed);
+            let ratio = left.as_secs_f64() / limit.as_secs_f64();
+
+            let gauge_area = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([
+                    Constraint::Percentage(30),
+                    Constraint::Percentage(40),
+                    Constraint::Percentage(30),
+                ])
+                .split(outer_chunks[4]);
+
+            let gauge = Gauge::default()
+                .gauge_style(Style::default().fg(Color::White).bg(Color::Black))
+                .label(format!("{}s", left.as_secs_f64().ceil() as u64))
+                .ratio(ratio.clamp(0.0, 1.0));
+            frame.render_widget(gauge, gauge_area[1]);
+        }
     }
 
     fn render_game_over_screen(&self, frame: &mut Frame) {
-        let lines = vec![
-            line_with_color("Verbal Memory", Color::White),
+        let mut lines = vec![
+            line_with_color(
+                format!("Verbal Memory • {}", if self.daily { "Daily challenge" } else { self.mode.name() }),
+                Color::White,
+            ),
             line_with_color(format!("{} words", self.score), Color::White)
                 .style(Style::default().add_modifier(Modifier::BOLD)),
-            line_with_color("Press to continue", Color::White),
         ];
+        if self.lives.is_none() {
+            lines.push(line_with_color(
+                format!("{}% accuracy over {} answers", self.accuracy_percent(), self.answered),
+                Color::White,
+            ));
+        }
+        if let Some(average) = self.average_response() {
+            lines.push(line_with_color(
+                format!("Average decision time: {} ms", average.as_millis()),
+                Color::White,
+            ));
+        }
+
+        let rank = self.last_record.as_ref().and_then(|last| {
+            self.leaderboard
+                .iter()
+                .position(|record| record.played_at == last.played_at)
+        });
+        match (rank, self.leaderboard.first()) {
+            // Today's run is ranked against the other days instead
+            _ if self.daily => lines.extend(self.daily_status.lines(self.score_unit(), Color::White)),
+            (Some(0), _) => lines.push(line_with_color("New best!", Color::Yellow)),
+            (Some(i), _) => lines.push(line_with_color(
+                format!("#{} on the {} leaderboard", i + 1, self.mode.name()),
+                Color::White,
+            )),
+            (None, Some(best)) => lines.push(line_with_color(
+                format!("Best: {} words", best.score),
+                Color::White,
+            )),
+            (None, None) => {}
+        }
+        if let Some(reference) = &self.reference {
+            lines.push(line_with_color(
+                format!("Better than {:.0}% of people", reference.percentile(self.score, self.ranking())),
+                Color::White,
+            ));
+        }
+        lines.push(line_with_color("Press to continue", Color::White));
 
         let size = frame.area();
 
@@ -299,33 +792,312 @@ impl VerbalMemory {
             .block(Block::default());
 
         frame.render_widget(paragraph, chunks[1]);
+
+        if let Some(reference) = &self.reference {
+            render_distribution(frame, chunks[2], reference, self.score);
+        }
+    }
+
+    /// Draws the lines in the middle of the screen, on the game's background.
+    fn render_centered(&self, frame: &mut Frame, lines: Vec<Line>) {
+        let size = frame.area();
+        let background = Block::default().style(Style::default().bg(Color::Cyan));
+        frame.render_widget(background, size);
+
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Percentage(30),
+                Constraint::Length(lines.len() as u16),
+                Constraint::Percentage(30),
+            ])
+            .split(size);
+        frame.render_widget(Paragraph::new(lines).alignment(Alignment::Center), chunks[1]);
     }
     
-    fn init_words_vec(&mut self) {
+    /// Loads the selected word list if needed, returns whether there are words to play with.
+    fn init_words_vec(&mut self) -> bool {
         if !self.words.is_empty() {
+            return true;
+        }
+
+        match self.sources[self.source_index].load() {
+            Ok(words) => {
+                self.words = words;
+                self.load_error = None;
+                true
+            }
+            Err(err) => {
+                self.load_error = Some(format!("Can't use this word list: {err}"));
+                false
+            }
+        }
+    }
+
+    fn select_source(&mut self, forward: bool) {
+        let len = self.sources.len();
+        self.source_index = if forward {
+            (self.source_index + 1) % len
+        } else {
+            (self.source_index + len - 1) % len
+        };
+        self.words.clear();
+        self.load_error = None;
+
+        let mut config: VerbalMemoryConfig = storage::load(CONFIG_FILE);
+        config.source = self.sources[self.source_index].clone();
+        // Forgetting the choice is not worth interrupting the game for
+        let _ = storage::save(CONFIG_FILE, &config);
+    }
+
+    fn select_mode(&mut self, forward: bool) {
+        let len = Mode::ALL.len();
+        let index = Mode::ALL.iter().position(|mode| *mode == self.mode).unwrap_or(0);
+        let next = if forward { index + 1 } else { index + len - 1 };
+        self.mode = Mode::ALL[next % len];
+        self.lives = self.mode.lives();
+        self.refresh_leaderboard();
+
+        let mut config: VerbalMemoryConfig = storage::load(CONFIG_FILE);
+        config.mode = self.mode;
+        let _ = storage::save(CONFIG_FILE, &config);
+    }
+
+    fn refresh_leaderboard(&mut self) {
+        self.leaderboard = history::leaderboard(GAME_ID, self.mode.id(), LEADERBOARD_SIZE);
+    }
+
+    fn is_out_of_time(&self) -> bool {
+        self.mode
+            .time_limit()
+            .is_some_and(|limit| self.elapsed >= limit)
+    }
+
+    fn accuracy_percent(&self) -> u32 {
+        if self.answered == 0 {
+            return 100;
+        }
+        self.score * 100 / self.answered
+    }
+
+    /// Ends the run, records it and shows the end screen.
+    fn finish_run(&mut self) {
+        self.state = GameState::End;
+        self.last_record = None;
+        self.reference = None;
+
+        if self.answered == 0 {
             return;
         }
+        self.reference = reference::distribution(GAME_ID, self.mode.id());
+
+        let record = self.session_record();
+        // A run that can't be saved is still shown, it just won't make the leaderboard
+        if history::record(&record).is_ok() {
+            self.last_record = Some(record);
+        }
+        self.refresh_leaderboard();
+        if self.daily {
+            self.daily_status = DailyStatus::load(GAME_ID, self.ranking());
+        }
+    }
+
+    fn session_record(&self) -> SessionRecord {
+        let mode = if self.daily { daily::MODE_ID } else { self.mode.id() };
+        let record = SessionRecord::new(GAME_ID, mode, self.score)
+            .with_trials(self.trials.clone())
+            .with_metric("answered", self.answered as f64)
+            .with_metric("accuracy", self.score as f64 / self.answered.max(1) as f64);
+        match self.average_response() {
+            Some(average) => record.with_metric("avg_response_ms", average.as_secs_f64() * 1000.0),
+            None => record,
+        }
+    }
 
-        let file_content = include_str!("../../assets/palabras.txt");
-        self.words = file_content
-            .lines()
-            .map(|line| line.trim().to_string())
-            .collect();
+    /// Whether giving up now would lose anything worth asking about.
+    fn has_progress(&self) -> bool {
+        matches!(self.state, GameState::Showing | GameState::Turn) && self.answered > 0
+    }
 
-        println!("Loaded {} words", self.words.len());
+    /// Keeps a trace of a run given up on, it never makes the leaderboard.
+    ///
+    /// Pass-and-play games are never recorded, given up on or not.
+    fn record_aborted(&mut self) {
+        if self.has_progress() && self.hot_seat.is_none() {
+            let _ = history::record(&self.session_record().aborted());
+        }
     }
 
+    fn abandon_run(&mut self) {
+        self.record_aborted();
+        self.reset_game();
+        self.state = GameState::Title;
+    }
 
     fn assign_random_word_based_on_progress(&mut self) {
-        let mut rng = rand::rng();
+        let unseen = self
+            .words
+            .iter()
+            .filter(|word| !self.words_seen.iter().any(|seen| &seen.word == *word));
+        let unseen_left = unseen.clone().count();
 
-        self.word_shown = if rng.random::<f64>() < 0.7 {
-            // 70% chance: pick from words Vec
-            self.words.choose(&mut rng).cloned()
+        let context = SelectionContext {
+            // The daily words get harder with each word shown rather than each right answer,
+            // so the sequence doesn't depend on how the player answers
+            score: if self.daily { self.turn } else { self.score },
+            turn: self.turn,
+            seen: &self.words_seen,
+            unseen_left,
+        };
+
+        self.word_shown = match self.selection.next(&context, &mut self.rng) {
+            NextWord::Seen(index) => self.words_seen.get(index).map(|seen| seen.word.clone()),
+            NextWord::New => unseen.choose(&mut self.rng).cloned(),
+        };
+
+        // Every word starts without a selection, a leftover one would answer for it
+        self.choice = None;
+        self.word_time = Duration::ZERO;
+        self.word_started = Some(replay::now());
+    }
+
+    /// Scores the answer given at `now` for the word on screen and moves on.
+    fn answer(&mut self, choice: Choice, now: Instant) {
+        let response = self.word_time
+            + self
+                .word_started
+                .map_or(Duration::ZERO, |started| now.saturating_duration_since(started));
+        self.response_times.push(response);
+
+        let seen_index = self.word_shown.as_ref().and_then(|word| {
+            self.words_seen.iter().position(|seen| &seen.word == word)
+        });
+        let is_seen = seen_index.is_some();
+
+        let correct = (choice == Choice::Seen) == is_seen;
+        self.trials.push(Trial {
+            item: self.word_shown.clone(),
+            answer: Some(if choice == Choice::Seen { "seen" } else { "new" }.to_string()),
+            correct: Some(correct),
+            ms: Some(response.as_secs_f64() * 1000.0),
+        });
+
+        // Adjust score and lives based on choice and correctness
+        self.answered += 1;
+        if correct {
+            self.score += 1;
+        } else if let Some(lives) = self.lives.as_mut() {
+            *lives = lives.saturating_sub(1);
+        }
+
+        // Whatever the answer, the word counts as seen from now on
+        match seen_index {
+            Some(index) => self.words_seen[index].last_shown = self.turn,
+            None => {
+                if let Some(word) = self.word_shown.clone() {
+                    self.words_seen.push(SeenWord {
+                        word,
+                        last_shown: self.turn,
+                    });
+                }
+            }
+        }
+        self.turn += 1;
+
+        if self.hot_seat.is_some() {
+            self.continue_turn();
+            return;
+        }
+
+        // Handle game over or progress
+        if self.lives == Some(0) || self.is_out_of_time() {
+            self.finish_run();
         } else {
-            // 30% chance: pick from words_seen HashSet
-            self.words_seen.iter().choose(&mut rng).cloned()
+            self.assign_random_word_based_on_progress();
+        }
+    }
+
+    /// Starts today's challenge: Classic rules on the bundled English words, drawn the same
+    /// for everyone today.
+    fn start_daily(&mut self) {
+        if self.daily_status.played {
+            return;
+        }
+        let words = match WordSource::Bundled(Language::English).load() {
+            Ok(words) => words,
+            Err(err) => {
+                self.load_error = Some(format!("Can't load the daily words: {err}"));
+                return;
+            }
         };
+        if !daily::start(GAME_ID) {
+            return;
+        }
+
+        self.reset_game();
+        self.daily = true;
+        self.mode = Mode::Classic;
+        self.lives = self.mode.lives();
+        self.words = words;
+        self.rng = daily::rng(GAME_ID);
+        self.assign_random_word_based_on_progress();
+        self.state = GameState::Showing;
+    }
+
+    /// Starts a pass-and-play game with the names typed, if the word list can be played.
+    fn start_hot_seat(&mut self) {
+        let names = self.names.as_ref().map(NameEntry::names).unwrap_or_default();
+        self.reset_game();
+        if self.init_words_vec() {
+            self.hot_seat = Some(HotSeat::new(names, self.mode.lives()));
+            self.state = GameState::Turn;
+        }
+    }
+
+    /// Hands the score and lives of the player whose turn it is to the game and shows a word.
+    fn start_turn(&mut self) {
+        let Some(hot_seat) = &self.hot_seat else {
+            return;
+        };
+        let player = hot_seat.current();
+        self.score = player.score;
+        self.lives = player.lives;
+        self.turn_answers = 0;
+        self.assign_random_word_based_on_progress();
+        self.state = GameState::Showing;
+    }
+
+    /// After a pass-and-play answer: keeps the player's score and lives, then shows the next
+    /// word or passes the keyboard once the turn is over, ending the game when everyone is out.
+    fn continue_turn(&mut self) {
+        let Some(hot_seat) = self.hot_seat.as_mut() else {
+            return;
+        };
+        self.turn_answers += 1;
+
+        let player = hot_seat.current_mut();
+        player.score = self.score;
+        player.lives = self.lives;
+        player.out = self.lives == Some(0);
+
+        if !player.out && self.turn_answers < TURN_WORDS {
+            self.assign_random_word_based_on_progress();
+            return;
+        }
+
+        hot_seat.last_turn = Some(match player.lives {
+            Some(0) => format!("{} is out with {} words", player.name, player.score),
+            Some(lives) => format!("{} has {} words and {lives} lives left", player.name, player.score),
+            None => format!("{} has {} words", player.name, player.score),
+        });
+        self.word_shown = None;
+        self.state = if hot_seat.advance() { GameState::Turn } else { GameState::Standings };
+    }
+
+    /// Mean time taken to answer a word in the current run.
+    fn average_response(&self) -> Option<Duration> {
+        let count = self.response_times.len() as u32;
+        (count > 0).then(|| self.response_times.iter().sum::<Duration>() / count)
     }
 
     fn reset_game(&mut self) {
@@ -333,15 +1105,38 @@ impl VerbalMemory {
     }
 
     fn quit_game(&mut self) {
+        self.state = GameState::Title;
         self.clear_progress();
         self.words.clear();
         self.quit = false;
     }
 
     fn clear_progress(&mut self) {
+        if self.daily {
+            // Back to the player's own mode and word list, and words nobody can predict
+            let config: VerbalMemoryConfig = storage::load(CONFIG_FILE);
+            self.mode = config.mode;
+            self.words.clear();
+            self.daily = false;
+            self.rng = GameRng::new();
+            self.daily_status = DailyStatus::load(GAME_ID, self.ranking());
+            self.refresh_leaderboard();
+        }
+        self.confirm = None;
         self.words_seen.clear();
-        self.lives = 3;
+        self.lives = self.mode.lives();
         self.score = 0;
+        self.answered = 0;
+        self.elapsed = Duration::ZERO;
         self.word_shown = None;
+        self.turn = 0;
+        self.choice = None;
+        self.word_started = None;
+        self.word_time = Duration::ZERO;
+        self.response_times.clear();
+        self.trials.clear();
+        self.names = None;
+        self.hot_seat = None;
+        self.turn_answers = 0;
     }
 }
@@ -0,0 +1,120 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Fewer words than this and the test would mostly be about repeats.
+const MIN_WORDS: usize = 50;
+
+/// Languages of the word lists shipped with the game.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Language {
+    English,
+    Spanish,
+    French,
+    German,
+}
+
+/// Where the words of a verbal memory session come from.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WordSource {
+    Bundled(Language),
+    File(PathBuf),
+}
+
+#[derive(Debug)]
+pub enum WordListError {
+    Io(PathBuf, std::io::Error),
+    TooFewWords(usize),
+}
+
+impl fmt::Display for WordListError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WordListError::Io(path, err) => write!(f, "could not read {}: {err}", path.display()),
+            WordListError::TooFewWords(count) => {
+                write!(f, "only {count} valid words, at least {MIN_WORDS} are needed")
+            }
+        }
+    }
+}
+
+impl Language {
+    pub const ALL: [Language; 4] = [
+        Language::English,
+        Language::Spanish,
+        Language::French,
+        Language::German,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Language::English => "English",
+            Language::Spanish => "Español",
+            Language::French => "Français",
+            Language::German => "Deutsch",
+        }
+    }
+
+    fn contents(&self) -> &'static str {
+        match self {
+            Language::English => include_str!("../../assets/words.txt"),
+            Language::Spanish => include_str!("../../assets/palabras.txt"),
+            Language::French => include_str!("../../assets/mots.txt"),
+            Language::German => include_str!("../../assets/woerter.txt"),
+        }
+    }
+}
+
+impl WordSource {
+    pub fn label(&self) -> String {
+        match self {
+            WordSource::Bundled(language) => language.name().to_string(),
+            WordSource::File(path) => path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_else(|| path.display().to_string()),
+        }
+    }
+
+    pub fn load(&self) -> Result<Vec<String>, WordListError> {
+        let words = match self {
+            WordSource::Bundled(language) => parse(language.contents()),
+            WordSource::File(path) => parse(&read(path)?),
+        };
+
+        if words.len() < MIN_WORDS {
+            return Err(WordListError::TooFewWords(words.len()));
+        }
+        Ok(words)
+    }
+}
+
+fn read(path: &Path) -> Result<String, WordListError> {
+    fs::read_to_string(path).map_err(|err| WordListError::Io(path.to_path_buf(), err))
+}
+
+/// Parses one word per line.
+///
+/// Entries are trimmed and lowercased, blank lines and `#` comments are skipped, and
+/// anything that isn't a single word (digits, spaces, punctuation other than `-` and `'`)
+/// is dropped. Duplicates are removed, keeping the first occurrence.
+fn parse(content: &str) -> Vec<String> {
+    let mut seen = HashSet::new();
+
+    content
+        .lines()
+        .map(|line| line.trim().to_lowercase())
+        .filter(|word| !word.is_empty() && !word.starts_with('#'))
+        .filter(|word| is_valid_word(word))
+        .filter(|word| seen.insert(word.clone()))
+        .collect()
+}
+
+fn is_valid_word(word: &str) -> bool {
+    word.chars().next().is_some_and(char::is_alphabetic)
+        && word
+            .chars()
+            .all(|c| c.is_alphabetic() || c == '-' || c == '\'')
+}
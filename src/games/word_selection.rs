@@ -0,0 +1,204 @@
+use rand::prelude::IndexedRandom;
+use rand::{Rng, RngCore};
+
+/// A word that has already been shown during the current run.
+pub struct SeenWord {
+    pub word: String,
+    /// Turn on which the word was shown most recently.
+    pub last_shown: u32,
+}
+
+/// What a selection strategy can look at to decide the next word.
+pub struct SelectionContext<'a> {
+    /// Correct answers so far.
+    pub score: u32,
+    /// Number of words shown so far, the next word is shown on this turn.
+    pub turn: u32,
+    /// Words already shown, in the order they were first shown.
+    pub seen: &'a [SeenWord],
+    /// How many words have not been shown yet.
+    pub unseen_left: usize,
+}
+
+/// The kind of word to show next.
+#[derive(Debug, PartialEq, Eq)]
+pub enum NextWord {
+    /// A word that has never been shown in this run.
+    New,
+    /// The seen word at this index of `SelectionContext::seen`.
+    Seen(usize),
+}
+
+/// Decides whether the next word is a repeat, and which one.
+///
+/// Strategies only pick the kind of word, the game is the one drawing a fresh word
+/// when `NextWord::New` is returned, so a "new" word is never one that was already shown.
+pub trait WordSelection {
+    fn next(&mut self, context: &SelectionContext, rng: &mut dyn RngCore) -> NextWord;
+}
+
+/// Difficulty that grows with the score.
+///
+/// Repeats get more frequent, they are drawn from a larger part of the history, and
+/// a word has to stay off screen for longer before it can come back.
+pub struct Progressive {
+    /// Chance of a repeat at score 0.
+    pub base_seen_chance: f64,
+    /// Chance of a repeat once the score reaches `full_difficulty_score`.
+    pub max_seen_chance: f64,
+    /// Score at which the difficulty stops increasing.
+    pub full_difficulty_score: u32,
+    /// How many of the most recently introduced words can repeat at score 0.
+    pub base_pool: usize,
+    /// Minimum number of turns between two showings of a word at score 0.
+    pub base_spacing: u32,
+}
+
+impl Default for Progressive {
+    fn default() -> Self {
+        Self {
+            base_seen_chance: 0.25,
+            max_seen_chance: 0.5,
+            full_difficulty_score: 60,
+            base_pool: 4,
+            base_spacing: 2,
+        }
+    }
+}
+
+impl Progressive {
+    /// How far along the difficulty curve the score is, from 0 to 1.
+    fn progress(&self, score: u32) -> f64 {
+        (score as f64 / self.full_difficulty_score.max(1) as f64).min(1.0)
+    }
+
+    fn seen_chance(&self, score: u32) -> f64 {
+        let progress = self.progress(score);
+        self.base_seen_chance + (self.max_seen_chance - self.base_seen_chance) * progress
+    }
+
+    /// Number of recently introduced words repeats are drawn from.
+    fn pool_size(&self, score: u32) -> usize {
+        self.base_pool + (score / 2) as usize
+    }
+
+    /// Minimum turns a word stays away before being shown again.
+    fn spacing(&self, score: u32) -> u32 {
+        self.base_spacing + (self.progress(score) * 10.0) as u32
+    }
+}
+
+impl WordSelection for Progressive {
+    fn next(&mut self, context: &SelectionContext, rng: &mut dyn RngCore) -> NextWord {
+        let wants_seen = context.unseen_left == 0 || rng.random_bool(self.seen_chance(context.score));
+        if !wants_seen {
+            return NextWord::New;
+        }
+
+        let spacing = self.spacing(context.score);
+        let pool_start = context.seen.len().saturating_sub(self.pool_size(context.score));
+        let candidates: Vec<usize> = (pool_start..context.seen.len())
+            .filter(|&i| context.turn - context.seen[i].last_shown >= spacing)
+            .collect();
+
+        match candidates.choose(rng) {
+            Some(&index) => NextWord::Seen(index),
+            // Nothing has been away long enough yet, only repeat if there is no other choice
+            None if context.unseen_left == 0 && !context.seen.is_empty() => {
+                NextWord::Seen(rng.random_range(0..context.seen.len()))
+            }
+            None => NextWord::New,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    fn seen(count: usize, last_shown: u32) -> Vec<SeenWord> {
+        (0..count)
+            .map(|i| SeenWord {
+                word: format!("word{i}"),
+                last_shown,
+            })
+            .collect()
+    }
+
+    /// Share of repeats picked at `score`, with every word long off screen.
+    fn seen_share(selection: &mut Progressive, score: u32, rng: &mut StdRng) -> f64 {
+        let seen = seen(50, 0);
+        let context = SelectionContext {
+            score,
+            turn: 1_000,
+            seen: &seen,
+            unseen_left: 100,
+        };
+        let draws = 10_000;
+        let repeats = (0..draws)
+            .filter(|_| matches!(selection.next(&context, rng), NextWord::Seen(_)))
+            .count();
+        repeats as f64 / draws as f64
+    }
+
+    #[test]
+    fn repeats_stay_off_screen_for_the_spacing() {
+        let mut selection = Progressive::default();
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut seen: Vec<SeenWord> = Vec::new();
+
+        // Every answer right, so the score follows the turns and the spacing grows
+        for turn in 0..200 {
+            let context = SelectionContext {
+                score: turn,
+                turn,
+                seen: &seen,
+                unseen_left: 1_000,
+            };
+            match selection.next(&context, &mut rng) {
+                NextWord::Seen(index) => {
+                    let away = turn - seen[index].last_shown;
+                    assert!(away >= selection.spacing(turn), "word {index} back after {away} turns on turn {turn}");
+                    seen[index].last_shown = turn;
+                }
+                NextWord::New => seen.push(SeenWord {
+                    word: format!("word{turn}"),
+                    last_shown: turn,
+                }),
+            }
+        }
+        assert!(seen.len() < 200, "no word was ever repeated");
+    }
+
+    #[test]
+    fn repeats_get_more_frequent_with_the_score() {
+        let mut selection = Progressive::default();
+        let mut rng = StdRng::seed_from_u64(7);
+
+        let easy = seen_share(&mut selection, 0, &mut rng);
+        let halfway = seen_share(&mut selection, 30, &mut rng);
+        let hard = seen_share(&mut selection, 200, &mut rng);
+        assert!((easy - 0.25).abs() < 0.02, "{easy}");
+        assert!((halfway - 0.375).abs() < 0.02, "{halfway}");
+        assert!((hard - 0.5).abs() < 0.02, "{hard}");
+    }
+
+    #[test]
+    fn only_repeats_once_every_word_was_shown() {
+        let mut selection = Progressive::default();
+        let mut rng = StdRng::seed_from_u64(7);
+        // Shown on the previous turn, nothing has been away long enough
+        let seen = seen(3, 9);
+        let context = SelectionContext {
+            score: 0,
+            turn: 10,
+            seen: &seen,
+            unseen_left: 0,
+        };
+        for _ in 0..100 {
+            assert!(matches!(selection.next(&context, &mut rng), NextWord::Seen(_)));
+        }
+    }
+}
@@ -0,0 +1,286 @@
+use crate::replay;
+use crate::signature::{self, Key};
+use crate::storage;
+use crate::team;
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::io;
+
+/// Every finished session, one JSON object per line, signed with the profile's key.
+const HISTORY_FILE: &str = "history.jsonl";
+/// Notes that the history was signed, kept apart from the key: deleting the key must not make
+/// unsigned lines trusted again.
+const SIGNED_FILE: &str = "history_signed.json";
+
+/// Which way the scores of a game go.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Ranking {
+    HigherIsBetter,
+    LowerIsBetter,
+}
+
+impl Ranking {
+    /// Orders scores best first.
+    pub fn compare(&self, a: u32, b: u32) -> Ordering {
+        match self {
+            Ranking::HigherIsBetter => b.cmp(&a),
+            Ranking::LowerIsBetter => a.cmp(&b),
+        }
+    }
+}
+
+/// A finished session of a game.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionRecord {
+    /// Identifier of the game, stable across versions.
+    pub game: String,
+    /// Game mode the session was played in, sessions of different modes are ranked apart.
+    pub mode: String,
+    pub score: u32,
+    pub played_at: DateTime<Local>,
+    /// Additional figures of the session, such as accuracy.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub metrics: BTreeMap<String, f64>,
+    /// The player gave up before the run was over, kept apart from finished runs.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub aborted: bool,
+    /// Merged from another machine's history, whose signature can't be checked here. Shown in
+    /// the stats, but kept off leaderboards, daily streaks and the team's scores.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub imported: bool,
+    /// Every attempt, word or number of the session in order, for analysis outside the game.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub trials: Vec<Trial>,
+}
+
+/// One attempt within a session. Each game fills in what makes sense for it.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Trial {
+    /// What was shown, such as a word or a number.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub item: Option<String>,
+    /// What the player answered.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub answer: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub correct: Option<bool>,
+    /// How long it took, such as a reaction time.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ms: Option<f64>,
+}
+
+impl SessionRecord {
+    pub fn new(game: &str, mode: &str, score: u32) -> Self {
+        Self {
+            game: game.to_string(),
+            mode: mode.to_string(),
+            score,
+            played_at: Local::now(),
+            metrics: BTreeMap::new(),
+            aborted: false,
+            imported: false,
+            trials: Vec::new(),
+        }
+    }
+
+    /// Marks the session as given up on.
+    pub fn aborted(mut self) -> Self {
+        self.aborted = true;
+        self
+    }
+
+    pub fn with_metric(mut self, name: &str, value: f64) -> Self {
+        self.metrics.insert(name.to_string(), value);
+        self
+    }
+
+    pub fn with_trials(mut self, trials: Vec<Trial>) -> Self {
+        self.trials = trials;
+        self
+    }
+
+    /// Whether both describe the same session, such as one already merged from another machine.
+    pub fn same_session(&self, other: &SessionRecord) -> bool {
+        self.id() == other.id()
+    }
+
+    pub fn id(&self) -> SessionId {
+        SessionId {
+            game: self.game.clone(),
+            mode: self.mode.clone(),
+            played_at: self.played_at,
+        }
+    }
+}
+
+/// What tells two sessions apart, whichever machine they were recorded on.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct SessionId {
+    pub game: String,
+    pub mode: String,
+    pub played_at: DateTime<Local>,
+}
+
+/// Keeps the session, and sends it to the team leaderboard in the background when finished.
+pub fn record(session: &SessionRecord) -> io::Result<()> {
+    append(session)?;
+    // A replay saves nothing, and sends nothing either
+    if !session.aborted && !replay::is_playing() {
+        team::sync_in_background();
+    }
+    Ok(())
+}
+
+/// Every session as it was recorded, those edited since are left out.
+pub fn sessions() -> Vec<SessionRecord> {
+    lines()
+        .into_iter()
+        .filter(|(_, intact)| *intact)
+        .filter_map(|(line, _)| serde_json::from_value(line).ok())
+        .collect()
+}
+
+/// How many sessions `sessions` leaves out because their line no longer matches its signature.
+pub fn edited() -> usize {
+    lines().iter().filter(|(_, intact)| !intact).count()
+}
+
+/// When the history was first signed, the sessions recorded before signing existed were trusted then.
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+struct Signed {
+    since: Option<DateTime<Local>>,
+}
+
+/// The lines of the history with whether each is as it was signed.
+///
+/// Without a key lines are only trusted if the history was never signed, they were written before signing existed.
+fn lines() -> Vec<(Value, bool)> {
+    let key = Key::load();
+    let lines = storage::load_lines(HISTORY_FILE);
+    let never_signed = key.is_none() && never_signed(&lines);
+    lines
+        .into_iter()
+        .map(|line| {
+            let intact = match &key {
+                Some(key) => key.verify(&line),
+                None => never_signed,
+            };
+            (line, intact)
+        })
+        .collect()
+}
+
+/// Whether the history predates signing: nothing in it is signed and it was never signed before.
+fn never_signed(lines: &[Value]) -> bool {
+    let signed: Signed = storage::load(SIGNED_FILE);
+    signed.since.is_none() && !lines.iter().any(signature::is_signed)
+}
+
+fn append(session: &SessionRecord) -> io::Result<()> {
+    let key = signing_key()?;
+    let line = serde_json::to_value(session).map_err(io::Error::other)?;
+    storage::append_line(HISTORY_FILE, &key.sign(line))
+}
+
+/// The profile's key, made on first use.
+///
+/// The first time ever, the sessions recorded before signing existed are trusted and signed. A key
+/// made again later trusts nothing that is already there, those lines count as edited.
+fn signing_key() -> io::Result<Key> {
+    if let Some(key) = Key::load() {
+        return Ok(key);
+    }
+    let key = Key::generate();
+    let lines = storage::load_lines(HISTORY_FILE);
+    let adopt = never_signed(&lines);
+    // Failing after this leaves lines the key doesn't match, edited rather than wrongly trusted
+    key.save()?;
+    if adopt {
+        let signed: Vec<Value> = lines.into_iter().map(|line| key.sign(line)).collect();
+        storage::save_lines(HISTORY_FILE, &signed)?;
+    }
+    if storage::load::<Signed>(SIGNED_FILE).since.is_none() {
+        storage::save(SIGNED_FILE, &Signed { since: Some(Local::now()) })?;
+    }
+    Ok(key)
+}
+
+/// Adds the sessions that aren't in the history yet, returns how many were added.
+///
+/// They are marked imported, signing them with this profile's key must not vouch for scores made elsewhere.
+pub fn merge(sessions: &[SessionRecord]) -> io::Result<usize> {
+    let mut known = self::sessions();
+    let mut added = 0;
+    for session in sessions {
+        if !known.iter().any(|existing| existing.same_session(session)) {
+            let mut session = session.clone();
+            session.imported = true;
+            append(&session)?;
+            known.push(session);
+            added += 1;
+        }
+    }
+    Ok(added)
+}
+
+/// Highest scoring finished sessions of a game mode, best first. Ties go to the earliest session.
+pub fn leaderboard(game: &str, mode: &str, limit: usize) -> Vec<SessionRecord> {
+    let mut sessions: Vec<SessionRecord> = sessions()
+        .into_iter()
+        .filter(|session| !session.aborted && !session.imported)
+        .filter(|session| session.game == game && session.mode == mode)
+        .collect();
+
+    sessions.sort_by(|a, b| b.score.cmp(&a.score).then(a.played_at.cmp(&b.played_at)));
+    sessions.truncate(limit);
+    sessions
+}
+
+fn is_false(value: &bool) -> bool {
+    !value
+}
+
+/// What the menu shows about the past sessions of a game.
+pub struct GameSummary {
+    /// Most recent finished session.
+    pub last: SessionRecord,
+    /// Best session in the mode of the last one, modes aren't comparable.
+    pub best: SessionRecord,
+    /// Scores of the latest sessions in that mode, oldest first.
+    pub recent: Vec<u32>,
+}
+
+/// Sums up the finished sessions of a game, `None` if it was never played to the end.
+pub fn summary(sessions: &[SessionRecord], game: &str, ranking: Ranking, recent: usize) -> Option<GameSummary> {
+    let finished: Vec<&SessionRecord> = sessions
+        .iter()
+        .filter(|session| !session.aborted && session.game == game)
+        .collect();
+
+    let last = finished.iter().max_by_key(|session| session.played_at)?;
+    let mut same_mode: Vec<&SessionRecord> = finished
+        .iter()
+        .copied()
+        .filter(|session| session.mode == last.mode)
+        .collect();
+    same_mode.sort_by_key(|session| session.played_at);
+
+    let best = same_mode
+        .iter()
+        .min_by(|a, b| ranking.compare(a.score, b.score).then(a.played_at.cmp(&b.played_at)))?;
+    let recent = same_mode[same_mode.len().saturating_sub(recent)..]
+        .iter()
+        .map(|session| session.score)
+        .collect();
+
+    Some(GameSummary {
+        last: (*last).clone(),
+        best: (*best).clone(),
+        recent,
+    })
+}
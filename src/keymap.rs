@@ -0,0 +1,357 @@
+use crate::storage;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io;
+
+/// User overrides of the default bindings, only the actions listed in it are changed.
+const KEYMAP_FILE: &str = "keybindings.json";
+
+/// Keys screens read themselves rather than through an action, with what they do.
+///
+/// Bound to an action, one of them would do two things on the same screen, so only the
+/// actions that have it by default keep it: those defaults were picked not to clash.
+const RESERVED: &[(KeyCode, &str)] = &[
+    (KeyCode::F(1), "the help screen"),
+    (KeyCode::Tab, "switching options on the menu, title screens and dialogs"),
+    (KeyCode::Backspace, "erasing typed text and the key bindings editor"),
+    (KeyCode::Delete, "the key bindings editor"),
+    (KeyCode::Char('y'), "answering yes in dialogs and Number Memory"),
+    (KeyCode::Char('n'), "answering no in dialogs and Number Memory"),
+    (KeyCode::Char('c'), "Reaction Time's calibration and the team server address"),
+    (KeyCode::Char('r'), "the key bindings editor and the team screen"),
+    (KeyCode::Char('v'), "Number Memory's variants and Reaction Time's versus mode"),
+    (KeyCode::Char('/'), "the menu's search"),
+    (KeyCode::Char('o'), "the menu's game settings"),
+    (KeyCode::Char('p'), "the menu's stats and hot-seat players"),
+    (KeyCode::Char('e'), "exporting from the stats screen"),
+    (KeyCode::Char('u'), "the menu's profile switch"),
+    (KeyCode::Char('t'), "the menu's team screen and daily challenges"),
+    (KeyCode::Char('q'), "stopping a replay"),
+    (KeyCode::Esc, "stopping a replay"),
+];
+
+/// Something the player can do, independent of the key it is bound to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    Up,
+    Down,
+    Left,
+    Right,
+    Confirm,
+    Back,
+    Pause,
+    Seen,
+    New,
+}
+
+impl Action {
+    pub const ALL: [Action; 9] = [
+        Action::Up,
+        Action::Down,
+        Action::Left,
+        Action::Right,
+        Action::Confirm,
+        Action::Back,
+        Action::Pause,
+        Action::Seen,
+        Action::New,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Action::Up => "Up",
+            Action::Down => "Down",
+            Action::Left => "Left",
+            Action::Right => "Right",
+            Action::Confirm => "Confirm",
+            Action::Back => "Back / quit",
+            Action::Pause => "Pause",
+            Action::Seen => "Seen (Verbal Memory)",
+            Action::New => "New (Verbal Memory)",
+        }
+    }
+
+    fn default_keys(&self) -> Vec<KeyCode> {
+        match self {
+            Action::Up => vec![KeyCode::Up, KeyCode::Char('w'), KeyCode::Char('k')],
+            Action::Down => vec![KeyCode::Down, KeyCode::Char('s'), KeyCode::Char('j')],
+            Action::Left => vec![KeyCode::Left, KeyCode::Char('a'), KeyCode::Char('h')],
+            Action::Right => vec![KeyCode::Right, KeyCode::Char('d'), KeyCode::Char('l')],
+            Action::Confirm => vec![KeyCode::Enter],
+            Action::Back => vec![KeyCode::Char('q'), KeyCode::Esc],
+            Action::Pause => vec![KeyCode::Esc],
+            Action::Seen => vec![KeyCode::Left, KeyCode::Char('a'), KeyCode::Char('s')],
+            Action::New => vec![KeyCode::Right, KeyCode::Char('d'), KeyCode::Char('n')],
+        }
+    }
+
+    /// Whether a key can be bound to both actions without making a screen ambiguous.
+    ///
+    /// Seen/New are only read while a word is shown, where nothing moves around, so they
+    /// can reuse the arrows. Pause wins over Back during a run and Back is used everywhere
+    /// else, so the two may share Esc.
+    fn can_share_keys(&self, other: Action) -> bool {
+        let answer = |action: Action| matches!(action, Action::Seen | Action::New);
+        let direction = |action: Action| matches!(action, Action::Up | Action::Down | Action::Left | Action::Right);
+        let leave = |action: Action| matches!(action, Action::Back | Action::Pause);
+
+        (answer(*self) && direction(other))
+            || (direction(*self) && answer(other))
+            || (leave(*self) && leave(other) && *self != other)
+    }
+
+    /// What the key is reserved for, if the action can't have it. See `RESERVED`.
+    fn reserved(&self, key: KeyCode) -> Option<&'static str> {
+        RESERVED
+            .iter()
+            .find(|(reserved, _)| *reserved == key)
+            .filter(|_| !self.default_keys().contains(&key))
+            .map(|(_, by)| *by)
+    }
+}
+
+/// A key bound to two actions that are read on the same screen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Conflict {
+    pub key: KeyCode,
+    pub first: Action,
+    pub second: Action,
+}
+
+impl fmt::Display for Conflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} is bound to both {} and {}",
+            key_label(self.key),
+            self.first.name(),
+            self.second.name()
+        )
+    }
+}
+
+/// Which keys trigger which action.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyMap {
+    bindings: BTreeMap<Action, Vec<KeyCode>>,
+}
+
+impl Default for KeyMap {
+    fn default() -> Self {
+        Self {
+            bindings: Action::ALL
+                .iter()
+                .map(|action| (*action, action.default_keys()))
+                .collect(),
+        }
+    }
+}
+
+impl KeyMap {
+    /// The default bindings with the saved overrides applied.
+    pub fn load() -> Self {
+        Self::with_overrides(storage::load(KEYMAP_FILE))
+    }
+
+    /// The default bindings with `overrides` applied, as saved by `save`.
+    ///
+    /// Keys that can't be read or are reserved are dropped, an action left without any key
+    /// keeps its defaults.
+    pub fn with_overrides(overrides: BTreeMap<Action, Vec<String>>) -> Self {
+        let mut keymap = Self::default();
+        for (action, names) in overrides {
+            let keys: Vec<KeyCode> = names
+                .iter()
+                .filter_map(|name| parse_key(name))
+                .filter(|key| action.reserved(*key).is_none())
+                .collect();
+            if !keys.is_empty() {
+                keymap.bindings.insert(action, keys);
+            }
+        }
+        keymap
+    }
+
+    /// Saves the actions whose keys differ from the defaults.
+    pub fn save(&self) -> io::Result<()> {
+        storage::save(KEYMAP_FILE, &self.overrides())
+    }
+
+    /// The actions whose keys differ from the defaults, by key name.
+    pub fn overrides(&self) -> BTreeMap<Action, Vec<String>> {
+        self.bindings
+            .iter()
+            .filter(|(action, keys)| **keys != action.default_keys())
+            .map(|(action, keys)| (*action, keys.iter().map(|key| key_name(*key)).collect()))
+            .collect()
+    }
+
+    pub fn keys(&self, action: Action) -> &[KeyCode] {
+        self.bindings.get(&action).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    /// Whether the key triggers the action. Keys held with Ctrl or Alt never do.
+    pub fn is(&self, action: Action, key_event: &KeyEvent) -> bool {
+        !key_event.modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT)
+            && self.keys(action).contains(&key_event.code)
+    }
+
+    /// The first of `among` the key triggers, for screens that only read some actions.
+    pub fn action(&self, key_event: &KeyEvent, among: &[Action]) -> Option<Action> {
+        among.iter().copied().find(|action| self.is(*action, key_event))
+    }
+
+    /// Binds one more key to the action, refused with the reason if it would make a screen
+    /// ambiguous.
+    pub fn bind(&mut self, action: Action, key: KeyCode) -> Result<(), String> {
+        if let Some(by) = action.reserved(key) {
+            return Err(format!("{} is reserved by {by}", key_label(key)));
+        }
+        if let Some(other) = self.conflicting_action(action, key) {
+            let conflict = Conflict {
+                key,
+                first: other,
+                second: action,
+            };
+            return Err(conflict.to_string());
+        }
+
+        let keys = self.bindings.entry(action).or_default();
+        if !keys.contains(&key) {
+            keys.push(key);
+        }
+        Ok(())
+    }
+
+    /// Removes the most recently added key of the action, the last key always stays.
+    pub fn unbind_last(&mut self, action: Action) -> bool {
+        match self.bindings.get_mut(&action) {
+            Some(keys) if keys.len() > 1 => {
+                keys.pop();
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn reset(&mut self, action: Action) {
+        self.bindings.insert(action, action.default_keys());
+    }
+
+    /// Every key bound to two actions that can't share it, hand-edited files may have some.
+    pub fn conflicts(&self) -> Vec<Conflict> {
+        let mut conflicts = Vec::new();
+        for (i, first) in Action::ALL.iter().enumerate() {
+            for second in &Action::ALL[i + 1..] {
+                if first.can_share_keys(*second) {
+                    continue;
+                }
+                for key in self.keys(*first) {
+                    if self.keys(*second).contains(key) {
+                        conflicts.push(Conflict {
+                            key: *key,
+                            first: *first,
+                            second: *second,
+                        });
+                    }
+                }
+            }
+        }
+        conflicts
+    }
+
+    /// Keys bound to the action, for hints and the help screen.
+    pub fn label(&self, action: Action) -> String {
+        self.keys(action)
+            .iter()
+            .map(|key| key_label(*key))
+            .collect::<Vec<_>>()
+            .join(" / ")
+    }
+
+    fn conflicting_action(&self, action: Action, key: KeyCode) -> Option<Action> {
+        Action::ALL
+            .iter()
+            .copied()
+            .filter(|other| *other != action && !action.can_share_keys(*other))
+            .find(|other| self.keys(*other).contains(&key))
+    }
+}
+
+/// How a key is written in the bindings file.
+pub fn key_name(key: KeyCode) -> String {
+    match key {
+        KeyCode::Char(' ') => "space".to_string(),
+        KeyCode::Char(c) => c.to_string(),
+        KeyCode::F(n) => format!("f{n}"),
+        KeyCode::Up => "up".to_string(),
+        KeyCode::Down => "down".to_string(),
+        KeyCode::Left => "left".to_string(),
+        KeyCode::Right => "right".to_string(),
+        KeyCode::Enter => "enter".to_string(),
+        KeyCode::Esc => "esc".to_string(),
+        KeyCode::Tab => "tab".to_string(),
+        KeyCode::BackTab => "backtab".to_string(),
+        KeyCode::Backspace => "backspace".to_string(),
+        KeyCode::Delete => "delete".to_string(),
+        KeyCode::Insert => "insert".to_string(),
+        KeyCode::Home => "home".to_string(),
+        KeyCode::End => "end".to_string(),
+        KeyCode::PageUp => "pageup".to_string(),
+        KeyCode::PageDown => "pagedown".to_string(),
+        other => format!("{other:?}").to_lowercase(),
+    }
+}
+
+/// Reads a key written by `key_name`, or a single character.
+pub fn parse_key(name: &str) -> Option<KeyCode> {
+    let mut chars = name.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        return Some(KeyCode::Char(c));
+    }
+
+    let key = match name.to_lowercase().as_str() {
+        "space" => KeyCode::Char(' '),
+        "up" => KeyCode::Up,
+        "down" => KeyCode::Down,
+        "left" => KeyCode::Left,
+        "right" => KeyCode::Right,
+        "enter" => KeyCode::Enter,
+        "esc" => KeyCode::Esc,
+        "tab" => KeyCode::Tab,
+        "backtab" => KeyCode::BackTab,
+        "backspace" => KeyCode::Backspace,
+        "delete" => KeyCode::Delete,
+        "insert" => KeyCode::Insert,
+        "home" => KeyCode::Home,
+        "end" => KeyCode::End,
+        "pageup" => KeyCode::PageUp,
+        "pagedown" => KeyCode::PageDown,
+        other => {
+            let n = other.strip_prefix('f')?.parse().ok()?;
+            KeyCode::F(n)
+        }
+    };
+    Some(key)
+}
+
+/// How a key is shown on screen.
+pub fn key_label(key: KeyCode) -> String {
+    match key {
+        KeyCode::Up => "↑".to_string(),
+        KeyCode::Down => "↓".to_string(),
+        KeyCode::Left => "←".to_string(),
+        KeyCode::Right => "→".to_string(),
+        KeyCode::Enter => "Enter".to_string(),
+        KeyCode::Esc => "Esc".to_string(),
+        KeyCode::Tab => "Tab".to_string(),
+        KeyCode::Backspace => "Backspace".to_string(),
+        KeyCode::Char(' ') => "Space".to_string(),
+        KeyCode::Char(c) => c.to_string(),
+        KeyCode::F(n) => format!("F{n}"),
+        other => key_name(other),
+    }
+}
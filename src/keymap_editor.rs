@@ -0,0 +1,169 @@
+use crate::games::Game;
+use crate::keymap::{key_label, Action, KeyMap};
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::layout::{Alignment, Constraint, Direction, Layout};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Wrap};
+use ratatui::Frame;
+
+/// Screen to change which keys trigger which action, run by the engine like a game.
+///
+/// The screen itself follows the bindings being edited, so a change can be tried right away.
+/// Changes are saved when leaving the screen, the menu picks them up from there.
+pub struct KeymapEditor {
+    keymap: KeyMap,    // Bindings being edited
+    selected: usize,   // Index of the action in `Action::ALL`
+    capturing: bool,   // The next key pressed is added to the selected action
+    message: String,   // Outcome of the last change
+    quit: bool,
+}
+
+impl KeymapEditor {
+    pub fn new() -> Self {
+        Self {
+            keymap: KeyMap::load(),
+            selected: 0,
+            capturing: false,
+            message: String::new(),
+            quit: false,
+        }
+    }
+
+    fn selected_action(&self) -> Action {
+        Action::ALL[self.selected]
+    }
+}
+
+impl Game for KeymapEditor {
+    fn name(&self) -> &str {
+        "⌨ Key Bindings"
+    }
+
+    fn description(&self) -> &str {
+        "Choose the keys for every action"
+    }
+
+    fn id(&self) -> &str {
+        "key_bindings"
+    }
+
+    fn help(&self) -> &str {
+        "Every action can be triggered by several keys.\n\
+         Keys shared by actions used on the same screen are refused, conflicts\n\
+         from a hand-edited keybindings.json are shown in red.\n\
+         \n\
+         up/down: pick an action • confirm: add a key, then press it\n\
+         Backspace: remove the last key • r: back to the default keys\n\
+         Back: save and return to the menu\n\
+         F1 and Ctrl+C are reserved and can't be bound."
+    }
+
+    fn handle_input(&mut self, key_event: KeyEvent, _keys: &KeyMap) {
+        let action = self.selected_action();
+
+        if self.capturing {
+            self.capturing = false;
+            self.message = match self.keymap.bind(action, key_event.code) {
+                Ok(()) => format!("{} added to {}", key_label(key_event.code), action.name()),
+                Err(reason) => format!("Not added: {reason}"),
+            };
+            return;
+        }
+
+        let among = [Action::Up, Action::Down, Action::Confirm, Action::Back];
+        match self.keymap.action(&key_event, &among) {
+            Some(Action::Up) => self.selected = self.selected.saturating_sub(1),
+            Some(Action::Down) => self.selected = (self.selected + 1).min(Action::ALL.len() - 1),
+            Some(Action::Confirm) => {
+                self.capturing = true;
+                self.message = format!("Press the key to add to {}", action.name());
+            }
+            Some(_) => self.quit = true,
+            None => match key_event.code {
+                KeyCode::Backspace | KeyCode::Delete => {
+                    self.message = if self.keymap.unbind_last(action) {
+                        format!("Removed the last key of {}", action.name())
+                    } else {
+                        "An action needs at least one key".to_string()
+                    };
+                }
+                KeyCode::Char('r') => {
+                    self.keymap.reset(action);
+                    self.message = format!("{} is back to its default keys", action.name());
+                }
+                _ => {}
+            },
+        }
+    }
+
+    fn render(&self, frame: &mut Frame) {
+        let layout = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(12), Constraint::Length(4), Constraint::Length(2)])
+            .split(frame.area());
+
+        let conflicts = self.keymap.conflicts();
+        let items: Vec<ListItem> = Action::ALL
+            .iter()
+            .map(|action| {
+                let conflicting = conflicts
+                    .iter()
+                    .any(|conflict| conflict.first == *action || conflict.second == *action);
+                let color = if conflicting { Color::Red } else { Color::Gray };
+
+                ListItem::new(Line::from(vec![
+                    Span::styled(
+                        format!("{:<22}", action.name()),
+                        Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD),
+                    ),
+                    Span::styled(self.keymap.label(*action), Style::default().fg(color)),
+                ]))
+            })
+            .collect();
+
+        let list = List::new(items)
+            .block(Block::default().title("⌨ Key Bindings").borders(Borders::ALL))
+            .highlight_style(
+                Style::default()
+                    .bg(Color::Blue)
+                    .fg(Color::White)
+                    .add_modifier(Modifier::BOLD),
+            )
+            .highlight_symbol(">> ");
+
+        let mut state = ListState::default();
+        state.select(Some(self.selected));
+        frame.render_stateful_widget(list, layout[0], &mut state);
+
+        let mut lines = vec![Line::from(self.message.clone())];
+        lines.extend(
+            conflicts
+                .iter()
+                .map(|conflict| Line::styled(conflict.to_string(), Style::default().fg(Color::Red))),
+        );
+        frame.render_widget(Paragraph::new(lines).wrap(Wrap { trim: true }), layout[1]);
+
+        let hint = Paragraph::new(format!(
+            "{} to add a key • Backspace to remove one • r to reset • {} to save and leave",
+            self.keymap.label(Action::Confirm),
+            self.keymap.label(Action::Back)
+        ))
+        .alignment(Alignment::Center);
+        frame.render_widget(hint, layout[2]);
+    }
+
+    fn wants_exit(&self) -> bool {
+        self.quit
+    }
+
+    fn restart(&mut self) {}
+
+    fn on_exit(&mut self) {
+        // Nothing to do about a failed save, the bindings just stay as they were
+        let _ = self.keymap.save();
+        self.capturing = false;
+        self.message.clear();
+        self.quit = false;
+    }
+}
@@ -1,29 +1,95 @@
+mod cli;
+mod daily;
+mod export;
 mod games;
+mod history;
+mod keymap;
+mod keymap_editor;
 mod menu;
+mod profile;
+mod profile_picker;
+mod reference;
+mod replay;
+mod server;
+mod settings_form;
+mod signature;
+mod stats;
+mod stats_screen;
+mod storage;
+mod team;
+mod team_screen;
 
+use crate::cli::{Cli, Command};
 use crate::menu::Menu;
+use crate::replay::Replay;
+use clap::Parser;
 use crossterm::{
+    event::{
+        DisableBracketedPaste, EnableBracketedPaste, KeyboardEnhancementFlags, PopKeyboardEnhancementFlags,
+        PushKeyboardEnhancementFlags,
+    },
     execute,
-    terminal::{EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode},
+    terminal::{
+        EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode, supports_keyboard_enhancement,
+    },
 };
 use ratatui::Terminal;
 use ratatui::backend::CrosstermBackend;
+use std::fs;
 use std::{error::Error, io};
 
 fn main() -> Result<(), Box<dyn Error>> {
+    let cli = Cli::parse();
+    let profile = match &cli.profile {
+        Some(name) => Some(profile::find(name).map_or_else(|| profile::create(name), Ok)?),
+        // Commands can't ask, they act for whoever played last
+        None if cli.command.is_some() => profile::last_used(),
+        None => None,
+    };
+    if let Some(profile) = &profile {
+        profile::activate(profile);
+    }
+
+    // A replay needs the terminal like the menu does, the file is read before taking it over
+    let replay = match &cli.command {
+        Some(Command::Replay { file, speed }) => Some((Replay::load(file.as_deref(), &cli)?, *speed)),
+        Some(command) => return run_command(command.clone()),
+        None => None,
+    };
+
     // setup termina
     enable_raw_mode()?;
     let mut stdout: io::Stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen)?;
+    // Pasted text comes in one piece instead of as keys typed faster than anyone can
+    execute!(stdout, EnterAlternateScreen, EnableBracketedPaste)?;
+    // Where the terminal can tell, a held key is reported as repeating rather than pressed again
+    let enhanced = supports_keyboard_enhancement().unwrap_or(false);
+    if enhanced {
+        execute!(
+            stdout,
+            PushKeyboardEnhancementFlags(
+                KeyboardEnhancementFlags::DISAMBIGUATE_ESCAPE_CODES
+                    | KeyboardEnhancementFlags::REPORT_EVENT_TYPES
+                    | KeyboardEnhancementFlags::REPORT_ALL_KEYS_AS_ESCAPE_CODES
+                    | KeyboardEnhancementFlags::REPORT_ALTERNATE_KEYS
+            )
+        )?;
+    }
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
     // Initialize Menu Screen here
-    let res = Menu::new().run(&mut terminal);
+    let res = match replay {
+        Some((replay, speed)) => replay.play(&mut terminal, speed),
+        None => Menu::new(&cli, profile).run(&mut terminal),
+    };
 
     // restore terminal
+    if enhanced {
+        execute!(terminal.backend_mut(), PopKeyboardEnhancementFlags)?;
+    }
     disable_raw_mode()?;
-    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+    execute!(terminal.backend_mut(), DisableBracketedPaste, LeaveAlternateScreen)?;
 
     if let Err(err) = res {
         println!("{:?}", err);
@@ -31,3 +97,30 @@ fn main() -> Result<(), Box<dyn Error>> {
 
     Ok(())
 }
+
+fn run_command(command: Command) -> Result<(), Box<dyn Error>> {
+    match command {
+        Command::Export { format, game, since, output } => {
+            let sessions = export::select(history::sessions(), game.as_deref(), since);
+            match output {
+                Some(path) => export::write(&sessions, format, fs::File::create(path)?)?,
+                None => match export::write(&sessions, format, io::stdout().lock()) {
+                    // Piped into `head` or the like, which stopped reading
+                    Err(err) if err.kind() == io::ErrorKind::BrokenPipe => {}
+                    result => result?,
+                },
+            }
+        }
+        Command::Import { file, format } => {
+            let format = format
+                .or_else(|| export::Format::of(&file))
+                .ok_or("can't tell the format from the file name, pass --format")?;
+            let sessions = export::read(format, fs::File::open(&file)?)?;
+            let added = history::merge(&sessions)?;
+            println!("{added} of {} sessions added, the others were already known", sessions.len());
+        }
+        Command::Serve { bind, port } => server::serve(&bind, port)?,
+        Command::Replay { .. } => unreachable!("replays are played by main"),
+    }
+    Ok(())
+}
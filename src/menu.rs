@@ -1,100 +1,82 @@
-use crate::games::{Game, get_all_games};
+use crate::cli::Cli;
+use crate::games::{Category, Engine, Game, get_all_games};
+use crate::history::{self, GameSummary};
+use crate::keymap::{Action, KeyMap};
+use crate::keymap_editor::KeymapEditor;
+use crate::profile::{self, Profile};
+use crate::profile_picker::ProfilePicker;
+use crate::replay;
+use crate::settings_form::SettingsForm;
+use crate::stats_screen::{StatsGame, StatsScreen};
+use crate::team_screen::TeamScreen;
+use chrono::{DateTime, Local};
 use crossterm::event;
 use crossterm::event::{Event, KeyCode, KeyEvent, KeyEventKind};
 use ratatui::Terminal;
 use ratatui::backend::CrosstermBackend;
 use ratatui::style::{Color, Modifier, Style};
 use ratatui::text::{Line, Span};
-use ratatui::widgets::{Block, Borders, List, ListItem, ListState, Paragraph};
+use ratatui::widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Sparkline, Wrap};
+use ratatui::Frame;
 use std::io;
-use ratatui::layout::{Alignment, Constraint, Direction, Layout};
+use ratatui::layout::{Alignment, Constraint, Direction, Layout, Rect};
+
+const BANNER: [&str; 3] = [
+    "╦═╗╔═╗╔╦╗╔═╗╔═╗╦  ╔═╗╦ ╦",
+    "╠╦╝╠═╣ ║ ╠═╣╠═╝║  ╠═╣╚╦╝",
+    "╩╚═╩ ╩ ╩ ╩ ╩╩  ╩═╝╩ ╩ ╩ ",
+];
+/// How many past sessions the trend line of the side panel covers.
+const RECENT_SESSIONS: usize = 20;
 
 pub struct Menu {
+    cli: Cli,                            // Kept to build the games again for another profile
+    profile: Option<Profile>,            // Who is playing, picked when the menu starts
     selected_index: u32,
     selectable_games: Vec<Box<dyn Game>>,
+    summaries: Vec<Option<GameSummary>>, // Past sessions of each game, same order as the games
+    keymap: KeyMap,
+    search: Option<String>,              // Query typed after `/`, while searching
+    settings: Option<(usize, SettingsForm)>, // Options being edited and the game they belong to
     quit: bool,
     in_game: bool,
+    editing_keys: bool,
+    viewing_stats: bool,
+    viewing_team: bool,
+    switching_profile: bool,
 }
 
 impl Menu {
-    pub fn new() -> Self {
-        Self {
+    /// `profile` is the player chosen on the command line, the menu asks for one otherwise.
+    pub fn new(cli: &Cli, profile: Option<Profile>) -> Self {
+        let mut menu = Self {
+            cli: cli.clone(),
+            profile,
             selected_index: 0,
-            selectable_games: get_all_games(),
+            selectable_games: get_all_games(cli),
+            summaries: Vec::new(),
+            keymap: KeyMap::load(),
+            search: None,
+            settings: None,
             quit: false,
             in_game: false,
-        }
+            editing_keys: false,
+            viewing_stats: false,
+            viewing_team: false,
+            switching_profile: false,
+        };
+        menu.refresh_summaries();
+        menu
     }
 
     pub fn run(&mut self, terminal: &mut Terminal<CrosstermBackend<io::Stdout>>) -> io::Result<()> {
+        if self.profile.is_none() {
+            self.pick_profile(terminal)?;
+        }
+
         while !self.quit {
             while !self.in_game {
-                terminal.draw(|frame| {
-                    let full_area = frame.area();
-
-                    // Default terminal background (no color)
-                    let bg_block = Block::default();
-                    frame.render_widget(bg_block, full_area);
-
-                    // Layout with header, list, and hint sections
-                    let layout = Layout::default()
-                        .direction(Direction::Vertical)
-                        .constraints([
-                            Constraint::Percentage(15),
-                            Constraint::Min(10),
-                            Constraint::Length(3),
-                        ])
-                        .split(full_area);
-
-                    // Game list block with a styled title
-                    let games_block = Block::default()
-                        .title(Span::styled(
-                            "🎮 Game Selector",
-                            Style::default()
-                                .fg(Color::White)
-                                .add_modifier(Modifier::BOLD),
-                        ))
-                        .borders(Borders::ALL)
-                        .border_style(Style::default().fg(Color::White));
-
-                    let items: Vec<ListItem> = self
-                        .selectable_games
-                        .iter()
-                        .map(|game| {
-                            ListItem::new(vec![
-                                Line::from(Span::styled(
-                                    game.name(),
-                                    Style::default()
-                                        .fg(Color::Yellow)
-                                        .add_modifier(Modifier::BOLD),
-                                )),
-                                Line::from(Span::styled(
-                                    game.description(),
-                                    Style::default().fg(Color::Gray),
-                                )),
-                                Line::from(""), // Spacer between items
-                            ])
-                        })
-                        .collect();
-
-                    let list = List::new(items)
-                        .block(games_block)
-                        .highlight_style(
-                            Style::default()
-                                .bg(Color::Blue)
-                                .fg(Color::White)
-                                .add_modifier(Modifier::BOLD),
-                        )
-                        .highlight_symbol(">> ");
-
-                    frame.render_stateful_widget(list, layout[1], &mut self.get_list_state());
-
-                    // Bottom hint text
-                    let hint = Paragraph::new("↑ ↓ to navigate • Enter to launch • q to quit")
-                        .style(Style::default().fg(Color::White)) // No background
-                        .alignment(Alignment::Center);
-                    frame.render_widget(hint, layout[2]);
-                })?;
+                terminal.draw(|frame| self.render(frame))?;
                 self.handle_events()?;
             }
 
@@ -102,8 +84,30 @@ impl Menu {
                 break;
             }
 
-            let game = &mut self.selectable_games[self.selected_index as usize];
-            let result = game.run(terminal);
+            let result = if self.editing_keys {
+                self.editing_keys = false;
+                let result = Engine::new(terminal, &self.keymap).run(&mut KeymapEditor::new());
+                self.keymap = KeyMap::load();
+                result
+            } else if self.switching_profile {
+                self.switching_profile = false;
+                self.pick_profile(terminal)
+            } else if self.viewing_stats {
+                self.viewing_stats = false;
+                Engine::new(terminal, &self.keymap).run(&mut StatsScreen::new(self.stats_games()))
+            } else if self.viewing_team {
+                self.viewing_team = false;
+                Engine::new(terminal, &self.keymap).run(&mut TeamScreen::new(self.stats_games()))
+            } else {
+                let index = self.visible_games()[self.selected_index as usize];
+                let game = self.selectable_games[index].as_mut();
+                replay::start_recording(game, &self.keymap);
+                let result = Engine::new(terminal, &self.keymap).run(game);
+                // A replay that can't be saved is no reason to stop playing
+                let _ = replay::finish_recording();
+                self.refresh_summaries();
+                result
+            };
 
             if result.is_err() {
                 self.quit = true;
@@ -114,18 +118,297 @@ impl Menu {
 
         Ok(())
     }
-    
-    fn get_list_state(&self) -> ListState {
+
+    /// Asks who is playing and loads their games, settings and bindings.
+    ///
+    /// Backing out keeps the current player, or leaves when there is none yet.
+    fn pick_profile(&mut self, terminal: &mut Terminal<CrosstermBackend<io::Stdout>>) -> io::Result<()> {
+        let mut picker = ProfilePicker::new(self.profile.as_ref());
+        Engine::new(terminal, &self.keymap).run(&mut picker)?;
+
+        match picker.chosen() {
+            Some(chosen) => {
+                profile::activate(chosen);
+                self.profile = Some(chosen.clone());
+                self.keymap = KeyMap::load();
+                self.selectable_games = get_all_games(&self.cli);
+                self.selected_index = 0;
+                self.search = None;
+                self.settings = None;
+                self.refresh_summaries();
+            }
+            None if self.profile.is_none() => {
+                self.in_game = true;
+                self.quit = true;
+            }
+            None => {}
+        }
+        Ok(())
+    }
+
+    fn refresh_summaries(&mut self) {
+        let sessions = history::sessions();
+        self.summaries = self
+            .selectable_games
+            .iter()
+            .map(|game| history::summary(&sessions, game.id(), game.ranking(), RECENT_SESSIONS))
+            .collect();
+    }
+
+    fn stats_games(&self) -> Vec<StatsGame> {
+        self.selectable_games
+            .iter()
+            .map(|game| StatsGame {
+                id: game.id().to_string(),
+                name: game.name().to_string(),
+                ranking: game.ranking(),
+                unit: game.score_unit().to_string(),
+            })
+            .collect()
+    }
+
+    /// Indices of the games to list, in display order.
+    ///
+    /// Games are grouped by category, or ordered by how well they match the search.
+    fn visible_games(&self) -> Vec<usize> {
+        let mut indices: Vec<usize> = (0..self.selectable_games.len()).collect();
+
+        match self.search.as_deref() {
+            Some(query) if !query.is_empty() => {
+                let mut scored: Vec<(i32, usize)> = indices
+                    .into_iter()
+                    .filter_map(|i| {
+                        let game = &self.selectable_games[i];
+                        let text = format!("{} {}", game.name(), game.category().name());
+                        fuzzy_score(query, &text).map(|score| (score, i))
+                    })
+                    .collect();
+                scored.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+                scored.into_iter().map(|(_, i)| i).collect()
+            }
+            _ => {
+                indices.sort_by_key(|i| {
+                    let category = self.selectable_games[*i].category();
+                    Category::ALL.iter().position(|c| *c == category)
+                });
+                indices
+            }
+        }
+    }
+
+    fn render(&self, frame: &mut Frame) {
+        let full_area = frame.area();
+
+        // Default terminal background (no color)
+        let bg_block = Block::default();
+        frame.render_widget(bg_block, full_area);
+
+        // Layout with header, games, and hint sections
+        let layout = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(BANNER.len() as u16 + 2),
+                Constraint::Min(10),
+                Constraint::Length(3),
+            ])
+            .split(full_area);
+
+        let columns = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(45), Constraint::Percentage(55)])
+            .split(layout[1]);
+
+        let visible = self.visible_games();
+        self.render_header(frame, layout[0]);
+        self.render_list(frame, columns[0], &visible);
+        match (&self.settings, visible.get(self.selected_index as usize)) {
+            (Some((_, form)), _) => form.render(frame, columns[1]),
+            (None, Some(&index)) => self.render_details(frame, columns[1], index),
+            (None, None) => frame.render_widget(Block::default().borders(Borders::ALL), columns[1]),
+        }
+
+        // Bottom hint text
+        let hint = match &self.search {
+            _ if self.settings.is_some() => format!(
+                "{} {} to pick an option • {} {} to change it • {} to save • {} to cancel",
+                self.keymap.label(Action::Up),
+                self.keymap.label(Action::Down),
+                self.keymap.label(Action::Left),
+                self.keymap.label(Action::Right),
+                self.keymap.label(Action::Confirm),
+                self.keymap.label(Action::Back)
+            ),
+            Some(_) => "Type to search • ↑ ↓ to navigate • Enter to launch • Esc to stop searching".to_string(),
+            None => format!(
+                "{} {} to navigate • {} to launch • o for settings • p for stats • t for team • / to search • Tab for key bindings • u to switch player • {} to quit",
+                self.keymap.label(Action::Up),
+                self.keymap.label(Action::Down),
+                self.keymap.label(Action::Confirm),
+                self.keymap.label(Action::Back)
+            ),
+        };
+        let hint = Paragraph::new(hint)
+            .style(Style::default().fg(Color::White)) // No background
+            .alignment(Alignment::Center)
+            .wrap(Wrap { trim: true });
+        frame.render_widget(hint, layout[2]);
+    }
+
+    fn render_header(&self, frame: &mut Frame, area: Rect) {
+        let banner_style = Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD);
+        let mut lines: Vec<Line> = BANNER
+            .iter()
+            .map(|line| Line::from(Span::styled(*line, banner_style)))
+            .collect();
+        let player = self.profile.as_ref().map_or("", |profile| profile.name.as_str());
+        lines.push(Line::from(Span::styled(
+            format!("Terminal minigames • {} games • 👤 {player}", self.selectable_games.len()),
+            Style::default().fg(Color::Gray),
+        )));
+
+        frame.render_widget(Paragraph::new(lines).alignment(Alignment::Center), area);
+    }
+
+    fn render_list(&self, frame: &mut Frame, area: Rect, visible: &[usize]) {
+        // Game list block with a styled title
+        let title = match &self.search {
+            Some(query) => format!("🔍 /{query}"),
+            None => "🎮 Game Selector".to_string(),
+        };
+        let games_block = Block::default()
+            .title(Span::styled(
+                title,
+                Style::default()
+                    .fg(Color::White)
+                    .add_modifier(Modifier::BOLD),
+            ))
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::White));
+
+        // Category headers are only shown when the list isn't ordered by a search
+        let grouped = self.search.as_deref().is_none_or(|query| query.is_empty());
+        let mut items: Vec<ListItem> = Vec::new();
+        let mut selected_item = None;
+        let mut category = None;
+
+        for (position, &index) in visible.iter().enumerate() {
+            let game = &self.selectable_games[index];
+            if grouped && category != Some(game.category()) {
+                category = Some(game.category());
+                items.push(ListItem::new(Line::from(Span::styled(
+                    game.category().name().to_uppercase(),
+                    Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD),
+                ))));
+            }
+
+            if position == self.selected_index as usize {
+                selected_item = Some(items.len());
+            }
+            items.push(ListItem::new(vec![
+                Line::from(Span::styled(
+                    game.name().to_string(),
+                    Style::default()
+                        .fg(Color::Yellow)
+                        .add_modifier(Modifier::BOLD),
+                )),
+                Line::from(Span::styled(
+                    game.description().to_string(),
+                    Style::default().fg(Color::Gray),
+                )),
+                Line::from(""), // Spacer between items
+            ]));
+        }
+
+        if items.is_empty() {
+            items.push(ListItem::new(Line::from(Span::styled(
+                "No game matches",
+                Style::default().fg(Color::Gray),
+            ))));
+        }
+
+        let list = List::new(items)
+            .block(games_block)
+            .highlight_style(
+                Style::default()
+                    .bg(Color::Blue)
+                    .fg(Color::White)
+                    .add_modifier(Modifier::BOLD),
+            )
+            .highlight_symbol(">> ");
+
         let mut state = ListState::default();
-        state.select(Some(self.selected_index as usize));
-        state
+        state.select(selected_item);
+        frame.render_stateful_widget(list, area, &mut state);
+    }
+
+    /// Side panel with the rules and past sessions of a game.
+    fn render_details(&self, frame: &mut Frame, area: Rect, index: usize) {
+        let game = &self.selectable_games[index];
+        let block = Block::default()
+            .title(format!(" {} ", game.category().name()))
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::White));
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+
+        let summary = self.summaries.get(index).and_then(Option::as_ref);
+        let parts = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(6), Constraint::Length(if summary.is_some() { 5 } else { 0 })])
+            .split(inner);
+
+        let bold = Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD);
+        let mut lines = vec![
+            Line::from(Span::styled(game.name().to_string(), bold)),
+            Line::from(Span::styled(game.description().to_string(), Style::default().fg(Color::Gray))),
+            Line::from(""),
+        ];
+        // The help opens with the rules, the controls come after the first blank line
+        lines.extend(
+            game.help()
+                .lines()
+                .take_while(|line| !line.trim().is_empty())
+                .map(|line| Line::from(line.trim().to_string())),
+        );
+        lines.push(Line::from(""));
+
+        let unit = game.score_unit();
+        match summary {
+            Some(summary) => {
+                lines.push(Line::from(format!(
+                    "Personal best ({}): {} {unit}",
+                    mode_label(&summary.best.mode),
+                    summary.best.score
+                )));
+                lines.push(Line::from(format!(
+                    "Last played {}: {} {unit}",
+                    ago(summary.last.played_at),
+                    summary.last.score
+                )));
+            }
+            None => lines.push(Line::from("Not played yet")),
+        }
+
+        frame.render_widget(Paragraph::new(lines).wrap(Wrap { trim: false }), parts[0]);
+
+        if let Some(summary) = summary {
+            let data: Vec<u64> = summary.recent.iter().map(|score| *score as u64).collect();
+            let trend = Sparkline::default()
+                .block(Block::default().title(format!("Last {} sessions", data.len())))
+                .data(&data)
+                .style(Style::default().fg(Color::Cyan));
+            frame.render_widget(trend, parts[1]);
+        }
     }
 
     fn handle_events(&mut self) -> io::Result<()> {
         match event::read()? {
             // it's important to check that the event is a key press event as
             // crossterm also emits key release and repeat events on Windows.
-            Event::Key(key_event) if key_event.kind == KeyEventKind::Press => {
+            // Holding a key still scrolls the list, only the games ignore auto-repeat
+            Event::Key(key_event)
+                if key_event.kind != KeyEventKind::Release && !matches!(key_event.code, KeyCode::Modifier(_)) =>
+            {
                 self.handle_key_event(key_event)
             }
             _ => {}
@@ -134,25 +417,133 @@ impl Menu {
     }
 
     fn handle_key_event(&mut self, key_event: KeyEvent) {
+        let count = self.visible_games().len() as u32;
+
+        if let Some((index, form)) = self.settings.as_mut() {
+            match form.handle_key(key_event, &self.keymap) {
+                Some(true) => match self.selectable_games[*index].apply_settings(form.settings()) {
+                    Ok(()) => self.settings = None,
+                    Err(error) => form.set_error(error),
+                },
+                Some(false) => self.settings = None,
+                None => {}
+            }
+            return;
+        }
+
+        if let Some(query) = self.search.as_mut() {
+            // Letters go into the query, so only keys that can't be typed move around
+            match key_event.code {
+                KeyCode::Esc => {
+                    self.search = None;
+                    self.selected_index = 0;
+                }
+                KeyCode::Enter if count > 0 => self.in_game = true,
+                KeyCode::Down if self.selected_index + 1 < count => self.selected_index += 1,
+                KeyCode::Up if self.selected_index > 0 => self.selected_index -= 1,
+                KeyCode::Backspace => {
+                    query.pop();
+                    self.selected_index = 0;
+                }
+                KeyCode::Char(c) => {
+                    query.push(c);
+                    self.selected_index = 0;
+                }
+                _ => {}
+            }
+            return;
+        }
+
         match key_event.code {
-            KeyCode::Esc | KeyCode::Char('q') => {
+            KeyCode::Tab => {
+                self.editing_keys = true;
                 self.in_game = true;
-                self.quit = true;
+                return;
             }
-            KeyCode::Char('s') | KeyCode::Down => {
-                if self.selected_index + 1 < self.selectable_games.len() as u32 {
-                    self.selected_index += 1;
-                }
+            KeyCode::Char('/') => {
+                self.search = Some(String::new());
+                return;
             }
-            KeyCode::Char('w') | KeyCode::Up => {
-                if self.selected_index > 0 {
-                    self.selected_index -= 1;
+            KeyCode::Char('u') => {
+                self.switching_profile = true;
+                self.in_game = true;
+                return;
+            }
+            KeyCode::Char('p') => {
+                self.viewing_stats = true;
+                self.in_game = true;
+                return;
+            }
+            KeyCode::Char('t') => {
+                self.viewing_team = true;
+                self.in_game = true;
+                return;
+            }
+            KeyCode::Char('o') => {
+                if let Some(&index) = self.visible_games().get(self.selected_index as usize) {
+                    let game = &self.selectable_games[index];
+                    self.settings = Some((index, SettingsForm::new(game.name(), game.settings())));
                 }
+                return;
             }
-            KeyCode::Enter => {
+            _ => {}
+        }
+
+        let among = [Action::Back, Action::Down, Action::Up, Action::Confirm];
+        match self.keymap.action(&key_event, &among) {
+            Some(Action::Back) => {
+                self.in_game = true;
+                self.quit = true;
+            }
+            Some(Action::Down) if self.selected_index + 1 < count => {
+                self.selected_index += 1;
+            }
+            Some(Action::Up) if self.selected_index > 0 => {
+                self.selected_index -= 1;
+            }
+            Some(Action::Confirm) => {
                 self.in_game = true;
             }
             _ => {}
         }
     }
 }
+
+/// How well `query` matches `text` as a subsequence, `None` when it doesn't match at all.
+///
+/// Letters that follow each other or start a word score higher, so "vm" ranks
+/// "Verbal Memory" above a name where the letters are scattered.
+fn fuzzy_score(query: &str, text: &str) -> Option<i32> {
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+    let mut score = 0;
+    let mut from = 0;
+    let mut previous: Option<usize> = None;
+
+    for wanted in query.to_lowercase().chars().filter(|c| !c.is_whitespace()) {
+        let found = from + text[from..].iter().position(|c| *c == wanted)?;
+        score += if previous.is_some_and(|p| p + 1 == found) { 3 } else { 1 };
+        if found == 0 || !text[found - 1].is_alphanumeric() {
+            score += 2;
+        }
+        previous = Some(found);
+        from = found + 1;
+    }
+    Some(score)
+}
+
+/// A mode id as shown to the player, `sudden_death` reads "sudden death".
+fn mode_label(mode: &str) -> String {
+    mode.replace('_', " ")
+}
+
+/// How long ago something happened, roughly.
+fn ago(at: DateTime<Local>) -> String {
+    let elapsed = Local::now().signed_duration_since(at);
+    match elapsed.num_minutes() {
+        ..1 => "just now".to_string(),
+        minutes @ 1..60 => format!("{minutes} min ago"),
+        minutes if minutes < 60 * 24 => format!("{} h ago", minutes / 60),
+        minutes if minutes < 60 * 24 * 30 => format!("{} days ago", minutes / (60 * 24)),
+        _ => format!("on {}", at.format("%Y-%m-%d")),
+    }
+}
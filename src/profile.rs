@@ -0,0 +1,135 @@
+use crate::storage;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+
+/// Every profile and the last one played, shared by all of them.
+const PROFILES_FILE: &str = "profiles.json";
+const MAX_NAME_LENGTH: usize = 20;
+/// A player's files and folders, the ones the first profile takes over from the data directory.
+/// Nothing else is moved, `RATAPLAY_HOME` can be a folder with other things in it.
+const PLAYER_FILES: &[&str] = &[
+    "reaction.json",
+    "verbal_memory.json",
+    "number_memory.json",
+    "keybindings.json",
+    "history.jsonl",
+    "exports",
+    "team.json",
+    "daily.json",
+    "replays",
+    "signing_key.json",
+    "history_signed.json",
+];
+
+/// A player, with their own scores, settings and key bindings.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Profile {
+    pub name: String,
+    /// Folder in `storage::PROFILES_DIR` the player's files are kept in, derived from the name once.
+    pub folder: String,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+struct Profiles {
+    profiles: Vec<Profile>,
+    /// Folder of the profile played last, picked first next time.
+    last: Option<String>,
+}
+
+pub fn list() -> Vec<Profile> {
+    storage::load_shared::<Profiles>(PROFILES_FILE).profiles
+}
+
+pub fn last_used() -> Option<Profile> {
+    let profiles: Profiles = storage::load_shared(PROFILES_FILE);
+    let last = profiles.last?;
+    profiles.profiles.into_iter().find(|profile| profile.folder == last)
+}
+
+/// The profile being played, `None` before one is picked.
+pub fn current() -> Option<Profile> {
+    let folder = storage::profile_folder()?;
+    list().into_iter().find(|profile| profile.folder == folder)
+}
+
+/// The profile with that name, ignoring case.
+pub fn find(name: &str) -> Option<Profile> {
+    list()
+        .into_iter()
+        .find(|profile| profile.name.to_lowercase() == name.trim().to_lowercase())
+}
+
+/// Adds a profile, refused with the reason if the name can't be used.
+///
+/// The first profile takes over the scores and settings kept before profiles existed.
+pub fn create(name: &str) -> Result<Profile, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("A profile needs a name".to_string());
+    }
+    if name.chars().count() > MAX_NAME_LENGTH {
+        return Err(format!("Names are at most {MAX_NAME_LENGTH} characters"));
+    }
+    if find(name).is_some() {
+        return Err(format!("{name} already exists"));
+    }
+
+    let mut profiles: Profiles = storage::load_shared(PROFILES_FILE);
+    let base = folder_name(name);
+    let folder = (1..)
+        .map(|n| if n == 1 { base.clone() } else { format!("{base}-{n}") })
+        .find(|folder| !profiles.profiles.iter().any(|profile| profile.folder == *folder))
+        .unwrap_or(base);
+    let profile = Profile {
+        name: name.to_string(),
+        folder,
+    };
+
+    if profiles.profiles.is_empty() {
+        adopt_unscoped_files(&profile).map_err(|err| format!("Can't move the existing scores: {err}"))?;
+    }
+    profiles.profiles.push(profile.clone());
+    storage::save_shared(PROFILES_FILE, &profiles).map_err(|err| format!("Can't save the profile: {err}"))?;
+    Ok(profile)
+}
+
+/// Makes the profile the one whose files are read and written, and the one picked next time.
+pub fn activate(profile: &Profile) {
+    storage::set_profile(Some(profile.folder.clone()));
+
+    let mut profiles: Profiles = storage::load_shared(PROFILES_FILE);
+    profiles.last = Some(profile.folder.clone());
+    // Only means the picker starts on another profile next time
+    let _ = storage::save_shared(PROFILES_FILE, &profiles);
+}
+
+/// A folder name made of the name's letters and digits, the rest turned into dashes.
+fn folder_name(name: &str) -> String {
+    let folder: String = name
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect();
+    let folder = folder.trim_matches('-');
+    if folder.is_empty() {
+        "player".to_string()
+    } else {
+        folder.to_string()
+    }
+}
+
+/// Moves the files written before profiles existed into the profile's folder.
+fn adopt_unscoped_files(profile: &Profile) -> io::Result<()> {
+    let data_dir = storage::data_dir();
+    let folder = data_dir.join(storage::PROFILES_DIR).join(&profile.folder);
+    for name in PLAYER_FILES {
+        let path = data_dir.join(name);
+        if path.exists() {
+            fs::create_dir_all(&folder)?;
+            fs::rename(&path, folder.join(name))?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,177 @@
+use crate::games::Game;
+use crate::keymap::{Action, KeyMap};
+use crate::profile::{self, Profile};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::layout::{Alignment, Constraint, Direction, Layout};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, List, ListItem, ListState, Paragraph};
+use ratatui::Frame;
+
+/// Screen to pick who is playing, or add a new player, run by the engine like a game.
+pub struct ProfilePicker {
+    profiles: Vec<Profile>,
+    selected: usize,            // Index in `profiles`, one past the end for a new profile
+    typing: Option<String>,     // Name of the profile being added
+    message: String,            // Why the last name was refused
+    chosen: Option<Profile>,
+    quit: bool,
+}
+
+impl ProfilePicker {
+    /// Starts on the current profile, or the one played last.
+    pub fn new(current: Option<&Profile>) -> Self {
+        let profiles = profile::list();
+        let start = current.cloned().or_else(profile::last_used);
+        let selected = start
+            .and_then(|start| profiles.iter().position(|profile| *profile == start))
+            .unwrap_or(0);
+
+        Self {
+            // No one to pick yet, go straight to typing a name
+            typing: profiles.is_empty().then(String::new),
+            profiles,
+            selected,
+            message: String::new(),
+            chosen: None,
+            quit: false,
+        }
+    }
+
+    /// The profile picked or created, `None` when the player backed out.
+    pub fn chosen(&self) -> Option<&Profile> {
+        self.chosen.as_ref()
+    }
+
+    fn handle_typing(&mut self, key_event: KeyEvent) {
+        let Some(name) = self.typing.as_mut() else {
+            return;
+        };
+
+        // Letters go into the name, so only keys that can't be typed do something else
+        match key_event.code {
+            KeyCode::Enter => match profile::create(name) {
+                Ok(profile) => {
+                    self.chosen = Some(profile);
+                    self.quit = true;
+                }
+                Err(err) => self.message = err,
+            },
+            KeyCode::Esc => {
+                self.typing = None;
+                self.message.clear();
+                // Without any profile there is nothing else to do here
+                self.quit = self.profiles.is_empty();
+            }
+            KeyCode::Backspace => {
+                name.pop();
+            }
+            KeyCode::Char(c) if !key_event.modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) => {
+                name.push(c);
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Game for ProfilePicker {
+    fn name(&self) -> &str {
+        "👤 Players"
+    }
+
+    fn description(&self) -> &str {
+        "Pick who is playing"
+    }
+
+    fn id(&self) -> &str {
+        "profiles"
+    }
+
+    fn help(&self) -> &str {
+        "Each player has their own scores, game settings and key bindings.\n\
+         \n\
+         up/down: pick a player • confirm: play as them\n\
+         New player: type a name, Enter to create it, Esc to cancel\n\
+         Back: keep the current player"
+    }
+
+    fn handle_input(&mut self, key_event: KeyEvent, keys: &KeyMap) {
+        if self.typing.is_some() {
+            self.handle_typing(key_event);
+            return;
+        }
+
+        let among = [Action::Up, Action::Down, Action::Confirm, Action::Back];
+        match keys.action(&key_event, &among) {
+            Some(Action::Up) => self.selected = self.selected.saturating_sub(1),
+            Some(Action::Down) => self.selected = (self.selected + 1).min(self.profiles.len()),
+            Some(Action::Confirm) => match self.profiles.get(self.selected) {
+                Some(profile) => {
+                    self.chosen = Some(profile.clone());
+                    self.quit = true;
+                }
+                None => self.typing = Some(String::new()),
+            },
+            Some(_) => self.quit = true,
+            None => {}
+        }
+    }
+
+    fn render(&self, frame: &mut Frame) {
+        let layout = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(6), Constraint::Length(3), Constraint::Length(2)])
+            .split(frame.area());
+
+        let mut items: Vec<ListItem> = self
+            .profiles
+            .iter()
+            .map(|profile| {
+                ListItem::new(Line::from(Span::styled(
+                    profile.name.clone(),
+                    Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD),
+                )))
+            })
+            .collect();
+        items.push(ListItem::new(Line::from(match &self.typing {
+            Some(name) => format!("New player: {name}▏"),
+            None => "+ New player".to_string(),
+        })));
+
+        let list = List::new(items)
+            .block(Block::default().title("👤 Who's playing?").borders(Borders::ALL))
+            .highlight_style(
+                Style::default()
+                    .bg(Color::Blue)
+                    .fg(Color::White)
+                    .add_modifier(Modifier::BOLD),
+            )
+            .highlight_symbol(">> ");
+
+        let mut state = ListState::default();
+        state.select(Some(if self.typing.is_some() { self.profiles.len() } else { self.selected }));
+        frame.render_stateful_widget(list, layout[0], &mut state);
+
+        frame.render_widget(
+            Paragraph::new(Line::styled(self.message.clone(), Style::default().fg(Color::Red))),
+            layout[1],
+        );
+
+        let hint = if self.typing.is_some() {
+            "Type a name • Enter to create • Esc to cancel"
+        } else {
+            "up/down to pick • confirm to play • Back to keep the current player"
+        };
+        frame.render_widget(Paragraph::new(hint).alignment(Alignment::Center), layout[2]);
+    }
+
+    fn wants_exit(&self) -> bool {
+        self.quit
+    }
+
+    fn restart(&mut self) {}
+
+    fn on_exit(&mut self) {
+        self.quit = false;
+    }
+}
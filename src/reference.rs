@@ -0,0 +1,94 @@
+use crate::history::Ranking;
+use crate::storage;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+/// Score distributions shipped with the game, approximated from public aggregate results.
+const BUNDLED: &str = include_str!("../assets/reference.json");
+/// Local distributions in the data directory, each game mode listed in it replaces the bundled one.
+const REFERENCE_FILE: &str = "reference.json";
+
+/// Distributions by game id, then by mode id.
+type References = BTreeMap<String, BTreeMap<String, Distribution>>;
+
+/// How the scores of a reference population spread, as a histogram.
+///
+/// Bucket `i` holds the scores from `start + i × bucket` up to the next bucket. Weights are
+/// relative, they don't have to add up to anything in particular.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Distribution {
+    pub start: u32,
+    pub bucket: u32,
+    pub weights: Vec<f64>,
+}
+
+impl Distribution {
+    /// Share of the population the score does better than, from 0 to 100.
+    ///
+    /// Scores within a bucket are spread evenly, and a score counts as being in the middle of
+    /// its unit, so a level everyone reaches ranks half of the people who stopped there.
+    pub fn percentile(&self, score: u32, ranking: Ranking) -> f64 {
+        let total: f64 = self.weights.iter().sum();
+        if total <= 0.0 || self.bucket == 0 {
+            return 0.0;
+        }
+
+        let position = (score as f64 + 0.5 - self.start as f64) / self.bucket as f64;
+        let below: f64 = self
+            .weights
+            .iter()
+            .enumerate()
+            .map(|(i, weight)| weight * (position - i as f64).clamp(0.0, 1.0))
+            .sum();
+        let share = 100.0 * below / total;
+
+        match ranking {
+            Ranking::HigherIsBetter => share,
+            Ranking::LowerIsBetter => 100.0 - share,
+        }
+    }
+
+    /// The middle of each bucket with its weight, to draw the curve.
+    pub fn curve(&self) -> Vec<(f64, f64)> {
+        self.weights
+            .iter()
+            .enumerate()
+            .map(|(i, weight)| {
+                let middle = self.start as f64 + (i as f64 + 0.5) * self.bucket as f64;
+                (middle, *weight)
+            })
+            .collect()
+    }
+
+    /// Lowest and highest score the histogram covers.
+    pub fn bounds(&self) -> (f64, f64) {
+        let end = self.start as u64 + self.bucket as u64 * self.weights.len() as u64;
+        (self.start as f64, end as f64)
+    }
+
+    /// Whether the histogram makes sense: buckets with a width, weights that are numbers and
+    /// not all zero, none negative, and an end a score can reach.
+    fn is_valid(&self) -> bool {
+        let end = u32::try_from(self.weights.len())
+            .ok()
+            .and_then(|len| self.bucket.checked_mul(len))
+            .and_then(|width| self.start.checked_add(width));
+        self.bucket > 0
+            && end.is_some()
+            && self.weights.iter().all(|weight| weight.is_finite() && *weight >= 0.0)
+            && self.weights.iter().sum::<f64>() > 0.0
+    }
+}
+
+/// The reference for a game mode, `None` when there is nothing to compare with.
+pub fn distribution(game: &str, mode: &str) -> Option<Distribution> {
+    let local: References = storage::load_shared(REFERENCE_FILE);
+    let find = |references: &References| references.get(game).and_then(|modes| modes.get(mode)).cloned();
+
+    // A hand-edited distribution that doesn't make sense leaves the bundled one in place
+    find(&local).filter(Distribution::is_valid).or_else(|| {
+        // The bundled file is known to be valid, a broken build only loses the comparison
+        let bundled: References = serde_json::from_str(BUNDLED).unwrap_or_default();
+        find(&bundled)
+    })
+}
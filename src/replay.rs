@@ -0,0 +1,430 @@
+use crate::cli::Cli;
+use crate::games::settings::SettingValue;
+use crate::games::{Engine, EventSource, Game, get_all_games};
+use crate::keymap::{self, Action, KeyMap};
+use crate::storage;
+use chrono::{DateTime, Local};
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use rand::rand_core::impls;
+use rand::rngs::StdRng;
+use rand::{RngCore, SeedableRng};
+use ratatui::Terminal;
+use ratatui::backend::CrosstermBackend;
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::collections::{BTreeMap, VecDeque};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// Folder of the profile the replays are kept in.
+const REPLAYS_DIR: &str = "replays";
+/// Replays kept per profile, the oldest are deleted first.
+const MAX_REPLAYS: usize = 50;
+/// Fastest playback, anything quicker can't be followed anyway.
+pub const MAX_SPEED: f64 = 16.0;
+/// Bumped when the file layout changes, older files are refused rather than misplayed.
+const FORMAT: u32 = 1;
+
+/// First line of a replay file: what is needed to set the game up as it was.
+#[derive(Serialize, Deserialize)]
+struct Header {
+    format: u32,
+    game: String,
+    /// Version of the program that recorded it, the games may have changed since.
+    version: String,
+    recorded_at: DateTime<Local>,
+    /// The game's settings, by key.
+    settings: BTreeMap<String, SettingValue>,
+    /// Key bindings that differed from the defaults.
+    keys: BTreeMap<Action, Vec<String>>,
+}
+
+/// One line of a replay file after the header. Times are in microseconds since the game was opened.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Entry {
+    /// Key name as in the bindings file, modifier bits, and 0 for a press, 1 a repeat, 2 a release.
+    Key(u64, String, u8, u8),
+    Resize(u64, u16, u16),
+    Paste(u64, String),
+    /// A number the game drew from its random source.
+    Random(u64, u64),
+}
+
+/// What the tape is doing on this thread, the games and the engine all run on the main one.
+enum Tape {
+    Off,
+    Recording {
+        started: Instant,
+        header: Header,
+        entries: Vec<Entry>,
+    },
+    Playing {
+        /// The virtual clock reads `origin + elapsed`, it only moves when the replay does.
+        origin: Instant,
+        elapsed: Duration,
+        draws: VecDeque<u64>,
+    },
+}
+
+thread_local! {
+    static TAPE: RefCell<Tape> = const { RefCell::new(Tape::Off) };
+}
+
+/// The time as games should see it: the real clock, or the replay's while one plays.
+pub fn now() -> Instant {
+    TAPE.with_borrow(|tape| match tape {
+        Tape::Playing { origin, elapsed, .. } => *origin + *elapsed,
+        _ => Instant::now(),
+    })
+}
+
+pub fn is_playing() -> bool {
+    TAPE.with_borrow(|tape| matches!(tape, Tape::Playing { .. }))
+}
+
+fn micros(duration: Duration) -> u64 {
+    duration.as_micros() as u64
+}
+
+/// Starts taping the game about to run, with the settings and keys it runs with.
+pub fn start_recording(game: &dyn Game, keys: &KeyMap) {
+    let header = Header {
+        format: FORMAT,
+        game: game.id().to_string(),
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        recorded_at: Local::now(),
+        settings: game
+            .settings()
+            .into_iter()
+            .map(|setting| (setting.key.to_string(), setting.value))
+            .collect(),
+        keys: keys.overrides(),
+    };
+    TAPE.set(Tape::Recording {
+        started: Instant::now(),
+        header,
+        entries: Vec::new(),
+    });
+}
+
+/// Tapes an event the engine is about to dispatch, nothing happens unless recording.
+pub fn record(event: &Event) {
+    TAPE.with_borrow_mut(|tape| {
+        let Tape::Recording { started, entries, .. } = tape else {
+            return;
+        };
+        let at = micros(started.elapsed());
+        match event {
+            Event::Key(key) => {
+                let kind = match key.kind {
+                    KeyEventKind::Press => 0,
+                    KeyEventKind::Repeat => 1,
+                    KeyEventKind::Release => 2,
+                };
+                entries.push(Entry::Key(at, keymap::key_name(key.code), key.modifiers.bits(), kind));
+            }
+            Event::Resize(width, height) => entries.push(Entry::Resize(at, *width, *height)),
+            Event::Paste(text) => entries.push(Entry::Paste(at, text.clone())),
+            _ => {}
+        }
+    });
+}
+
+/// Stops taping and saves the replay into the profile, dropping the oldest past `MAX_REPLAYS`.
+pub fn finish_recording() -> io::Result<()> {
+    let Tape::Recording { header, entries, .. } = TAPE.replace(Tape::Off) else {
+        return Ok(());
+    };
+    // Opening a game and backing out at once isn't worth keeping
+    let presses = entries.iter().filter(|entry| matches!(entry, Entry::Key(_, _, _, 0))).count();
+    if presses < 2 {
+        return Ok(());
+    }
+
+    let mut content = serde_json::to_string(&header).map_err(io::Error::other)?;
+    content.push('\n');
+    for entry in &entries {
+        content.push_str(&serde_json::to_string(entry).map_err(io::Error::other)?);
+        content.push('\n');
+    }
+
+    let dir = storage::profile_dir().join(REPLAYS_DIR);
+    fs::create_dir_all(&dir)?;
+    // Named after the time first so that sorting the names sorts the replays
+    let name = format!("{}-{}.jsonl", header.recorded_at.format("%Y%m%d-%H%M%S"), header.game);
+    fs::write(dir.join(name), content)?;
+
+    let files = files();
+    for old in &files[..files.len().saturating_sub(MAX_REPLAYS)] {
+        fs::remove_file(old)?;
+    }
+    Ok(())
+}
+
+/// Replay files of the current profile, oldest first.
+fn files() -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = fs::read_dir(storage::profile_dir().join(REPLAYS_DIR))
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                .filter(|path| path.extension().is_some_and(|extension| extension == "jsonl"))
+                .collect()
+        })
+        .unwrap_or_default();
+    files.sort();
+    files
+}
+
+/// Random source of the games: draws are taped while recording and taken from the tape while playing.
+///
+/// Every draw goes through `next_u64`, so the tape holds one kind of number whatever the game asks for.
+pub struct GameRng {
+    inner: StdRng,
+}
+
+impl GameRng {
+    pub fn new() -> Self {
+        Self {
+            inner: StdRng::from_os_rng(),
+        }
+    }
+
+    pub fn seeded(seed: u64) -> Self {
+        Self {
+            inner: StdRng::seed_from_u64(seed),
+        }
+    }
+}
+
+impl RngCore for GameRng {
+    fn next_u32(&mut self) -> u32 {
+        (self.next_u64() >> 32) as u32
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        TAPE.with_borrow_mut(|tape| match tape {
+            // A tape that runs out falls back on chance, the game plays on all the same
+            Tape::Playing { draws, .. } => draws.pop_front().unwrap_or_else(|| self.inner.next_u64()),
+            Tape::Recording { started, entries, .. } => {
+                let value = self.inner.next_u64();
+                entries.push(Entry::Random(micros(started.elapsed()), value));
+                value
+            }
+            Tape::Off => self.inner.next_u64(),
+        })
+    }
+
+    fn fill_bytes(&mut self, dst: &mut [u8]) {
+        impls::fill_bytes_via_next(self, dst)
+    }
+}
+
+/// A recorded session ready to be played back.
+pub struct Replay {
+    header: Header,
+    game: Box<dyn Game>,
+    events: VecDeque<(Duration, Event)>,
+    draws: VecDeque<u64>,
+}
+
+impl Replay {
+    /// Reads a replay file, the latest one of the current profile without `path`.
+    pub fn load(path: Option<&Path>, cli: &Cli) -> Result<Self, String> {
+        let path = match path {
+            Some(path) => path.to_path_buf(),
+            None => files().pop().ok_or("no replays yet, games are recorded as they are played")?,
+        };
+        let content = fs::read_to_string(&path).map_err(|err| format!("can't read {}: {err}", path.display()))?;
+
+        let mut lines = content.lines();
+        let header: Header = lines
+            .next()
+            .and_then(|line| serde_json::from_str(line).ok())
+            .ok_or_else(|| format!("{} is not a replay", path.display()))?;
+        if header.format != FORMAT {
+            return Err(format!("{} was recorded by rataplay {}, which this one can't play", path.display(), header.version));
+        }
+        let game = get_all_games(cli)
+            .into_iter()
+            .find(|game| game.id() == header.game)
+            .ok_or_else(|| format!("{} is a replay of {}, which isn't a game here", path.display(), header.game))?;
+
+        let mut events = VecDeque::new();
+        let mut draws = VecDeque::new();
+        // Lines that can't be read are skipped, a half-written last line only loses that entry
+        for entry in lines.filter_map(|line| serde_json::from_str(line).ok()) {
+            match entry {
+                Entry::Key(at, name, modifiers, kind) => {
+                    let Some(code) = keymap::parse_key(&name) else {
+                        continue;
+                    };
+                    let kind = match kind {
+                        1 => KeyEventKind::Repeat,
+                        2 => KeyEventKind::Release,
+                        _ => KeyEventKind::Press,
+                    };
+                    let key = KeyEvent::new_with_kind(code, KeyModifiers::from_bits_truncate(modifiers), kind);
+                    events.push_back((Duration::from_micros(at), Event::Key(key)));
+                }
+                Entry::Resize(at, width, height) => {
+                    events.push_back((Duration::from_micros(at), Event::Resize(width, height)));
+                }
+                Entry::Paste(at, text) => events.push_back((Duration::from_micros(at), Event::Paste(text))),
+                Entry::Random(_, value) => draws.push_back(value),
+            }
+        }
+
+        Ok(Self {
+            header,
+            game,
+            events,
+            draws,
+        })
+    }
+
+    /// Runs the game again on the taped keys and draws, `speed` times as fast as it was played.
+    ///
+    /// Nothing is saved meanwhile: the run isn't added to the history and settings stay as they are.
+    pub fn play(mut self, terminal: &mut Terminal<CrosstermBackend<io::Stdout>>, speed: f64) -> io::Result<()> {
+        storage::set_read_only(true);
+
+        let mut settings = self.game.settings();
+        for setting in &mut settings {
+            if let Some(value) = self.header.settings.get(setting.key) {
+                setting.value = *value;
+            }
+        }
+        // Values this version refuses leave the current ones, the replay may drift from there
+        let _ = self.game.apply_settings(&settings);
+        let keys = KeyMap::with_overrides(self.header.keys.clone());
+
+        TAPE.set(Tape::Playing {
+            origin: Instant::now(),
+            elapsed: Duration::ZERO,
+            draws: std::mem::take(&mut self.draws),
+        });
+        let playback = Playback {
+            title: format!("{} from {}", self.game.name(), self.header.recorded_at.format("%Y-%m-%d %H:%M")),
+            length: self.events.back().map_or(Duration::ZERO, |(at, _)| *at),
+            events: std::mem::take(&mut self.events),
+            speed,
+            stopped: false,
+        };
+        let result = Engine::with_events(terminal, &keys, Box::new(playback)).run(self.game.as_mut());
+
+        TAPE.set(Tape::Off);
+        storage::set_read_only(false);
+        result
+    }
+}
+
+/// Where the replay stands on the virtual clock.
+fn clock() -> Duration {
+    TAPE.with_borrow(|tape| match tape {
+        Tape::Playing { elapsed, .. } => *elapsed,
+        _ => Duration::ZERO,
+    })
+}
+
+fn set_clock(at: Duration) {
+    TAPE.with_borrow_mut(|tape| {
+        if let Tape::Playing { elapsed, .. } = tape {
+            *elapsed = at;
+        }
+    });
+}
+
+/// Feeds the engine the taped events at their time, while the real keyboard can stop it.
+struct Playback {
+    events: VecDeque<(Duration, Event)>,
+    speed: f64,
+    length: Duration,
+    title: String,
+    stopped: bool,
+}
+
+impl Playback {
+    /// Waits `wait` of real time, `None` until a key is pressed. `true` if the viewer asked to stop.
+    fn wait_for_stop(&self, wait: Option<Duration>) -> io::Result<bool> {
+        let deadline = wait.map(|wait| Instant::now() + wait);
+        loop {
+            let left = deadline.map_or(Duration::from_secs(1), |deadline| {
+                deadline.saturating_duration_since(Instant::now())
+            });
+            if event::poll(left)? {
+                if let Event::Key(key) = event::read()? {
+                    // Once the tape is over any key leaves, before that only the keys that mean so
+                    let stops = self.events.is_empty()
+                        || key.code == KeyCode::Esc
+                        || key.code == KeyCode::Char('q')
+                        || (key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL));
+                    if key.kind == KeyEventKind::Press && stops {
+                        return Ok(true);
+                    }
+                }
+            }
+            if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                return Ok(false);
+            }
+        }
+    }
+}
+
+impl EventSource for Playback {
+    fn poll(&mut self, timeout: Option<Duration>) -> io::Result<bool> {
+        if self.stopped {
+            return Ok(true);
+        }
+
+        let elapsed = clock();
+        let next = self.events.front().map(|(at, _)| *at);
+        let target = match (next, timeout) {
+            (Some(at), Some(timeout)) => at.min(elapsed + timeout),
+            (Some(at), None) => at,
+            (None, Some(timeout)) => elapsed + timeout,
+            // Nothing left to play, the last screen stays up until a key is pressed
+            (None, None) => {
+                self.stopped = self.wait_for_stop(None)?;
+                return Ok(true);
+            }
+        };
+
+        let target = target.max(elapsed);
+        if self.wait_for_stop(Some((target - elapsed).div_f64(self.speed)))? {
+            self.stopped = true;
+            return Ok(true);
+        }
+        set_clock(target);
+        Ok(next.is_some_and(|at| at <= target))
+    }
+
+    fn read(&mut self) -> io::Result<Event> {
+        match self.events.pop_front() {
+            Some((at, event)) if !self.stopped => {
+                set_clock(at.max(clock()));
+                Ok(event)
+            }
+            // Stopping goes through the engine's own way back to the menu
+            _ => {
+                self.stopped = true;
+                Ok(Event::Key(KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL)))
+            }
+        }
+    }
+
+    fn status(&self) -> Option<String> {
+        if self.events.is_empty() {
+            return Some(format!("■ End of the replay of {} • any key to leave", self.title));
+        }
+        let progress = clock().as_secs_f64() / self.length.as_secs_f64().max(f64::EPSILON);
+        Some(format!(
+            "▶ Replay of {} • {:.0}% • {}× • Esc to stop",
+            self.title,
+            (progress * 100.0).min(100.0),
+            self.speed
+        ))
+    }
+}
@@ -0,0 +1,216 @@
+use crate::history::{Ranking, SessionRecord};
+use crate::storage;
+use crate::team::{MAX_MESSAGE_BYTES, ModeBoard, Request, Response, TeamEntry};
+use std::collections::BTreeMap;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+/// Every session submitted to the server, one JSON object per line.
+const SCORES_FILE: &str = "team_scores.jsonl";
+const MAX_PLAYER_LENGTH: usize = 40;
+/// A client that stops talking mid-request is dropped after this long.
+const IO_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Runs the team leaderboard server until the process is stopped.
+///
+/// Each connection carries one request line and gets one response line, so any
+/// number of players can submit and read at the same time.
+pub fn serve(bind: &str, port: u16) -> io::Result<()> {
+    let listener = TcpListener::bind((bind, port))?;
+    let entries: Vec<TeamEntry> = storage::load_shared_lines(SCORES_FILE);
+    println!(
+        "Team leaderboard on {}, {} sessions in {}",
+        listener.local_addr()?,
+        entries.len(),
+        storage::data_dir().join(SCORES_FILE).display()
+    );
+    listen(listener, entries)
+}
+
+/// Answers the connections of a bound listener, starting from the sessions already stored.
+fn listen(listener: TcpListener, entries: Vec<TeamEntry>) -> io::Result<()> {
+    let entries = Arc::new(Mutex::new(entries));
+    for stream in listener.incoming() {
+        let Ok(stream) = stream else {
+            continue;
+        };
+        let entries = Arc::clone(&entries);
+        thread::spawn(move || {
+            let peer = stream.peer_addr().map(|peer| peer.to_string()).unwrap_or_default();
+            if let Err(err) = handle(stream, &entries) {
+                eprintln!("{peer}: {err}");
+            }
+        });
+    }
+    Ok(())
+}
+
+fn handle(stream: TcpStream, entries: &Mutex<Vec<TeamEntry>>) -> io::Result<()> {
+    stream.set_read_timeout(Some(IO_TIMEOUT))?;
+    stream.set_write_timeout(Some(IO_TIMEOUT))?;
+
+    let mut line = String::new();
+    BufReader::new((&stream).take(MAX_MESSAGE_BYTES)).read_line(&mut line)?;
+    let response = match serde_json::from_str(&line) {
+        Ok(request) => answer(request, entries),
+        Err(err) => Response::Error {
+            message: format!("not a request: {err}"),
+        },
+    };
+
+    let mut line = serde_json::to_string(&response).map_err(io::Error::from)?;
+    line.push('\n');
+    (&stream).write_all(line.as_bytes())
+}
+
+fn answer(request: Request, entries: &Mutex<Vec<TeamEntry>>) -> Response {
+    let mut entries = entries.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    match request {
+        Request::Submit { player, sessions } => {
+            let player = player.trim().to_string();
+            if player.is_empty() || player.chars().count() > MAX_PLAYER_LENGTH {
+                return Response::Error {
+                    message: format!("player names are 1 to {MAX_PLAYER_LENGTH} characters"),
+                };
+            }
+
+            let mut added = 0;
+            for session in sessions.into_iter().filter(|session| !session.aborted) {
+                let known = entries
+                    .iter()
+                    .any(|entry| entry.player == player && entry.session.same_session(&session));
+                if known {
+                    continue;
+                }
+                let entry = TeamEntry {
+                    player: player.clone(),
+                    session,
+                };
+                if let Err(err) = storage::append_shared_line(SCORES_FILE, &entry) {
+                    return Response::Error {
+                        message: format!("can't store the scores: {err}"),
+                    };
+                }
+                entries.push(entry);
+                added += 1;
+            }
+            Response::Submitted { added }
+        }
+        Request::Leaderboard { game, ranking, limit } => Response::Leaderboard {
+            modes: leaderboard(&entries, &game, ranking, limit),
+        },
+    }
+}
+
+/// Best session of each player in every mode of a game, the most played modes first.
+fn leaderboard(entries: &[TeamEntry], game: &str, ranking: Ranking, limit: usize) -> Vec<ModeBoard> {
+    let mut modes: BTreeMap<&str, (usize, BTreeMap<&str, &SessionRecord>)> = BTreeMap::new();
+    for entry in entries.iter().filter(|entry| entry.session.game == game) {
+        let (count, best) = modes.entry(&entry.session.mode).or_default();
+        *count += 1;
+        let better = best.get(entry.player.as_str()).is_none_or(|current| {
+            ranking
+                .compare(entry.session.score, current.score)
+                .then(entry.session.played_at.cmp(&current.played_at))
+                .is_lt()
+        });
+        if better {
+            best.insert(&entry.player, &entry.session);
+        }
+    }
+
+    let mut boards: Vec<(usize, ModeBoard)> = modes
+        .into_iter()
+        .map(|(mode, (count, best))| {
+            let mut entries: Vec<TeamEntry> = best
+                .into_iter()
+                .map(|(player, session)| TeamEntry {
+                    player: player.to_string(),
+                    session: session.clone(),
+                })
+                .collect();
+            entries.sort_by(|a, b| {
+                ranking
+                    .compare(a.session.score, b.session.score)
+                    .then(a.session.played_at.cmp(&b.session.played_at))
+            });
+            entries.truncate(limit);
+            (count, ModeBoard { mode: mode.to_string(), entries })
+        })
+        .collect();
+    boards.sort_by_key(|(count, _)| std::cmp::Reverse(*count));
+    boards.into_iter().map(|(_, board)| board).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::team;
+    use chrono::{Local, TimeZone};
+
+    fn session(mode: &str, score: u32, minute: u32) -> SessionRecord {
+        let mut session = SessionRecord::new("server-test", mode, score);
+        session.played_at = Local.with_ymd_and_hms(2026, 3, 1, 12, minute, 0).unwrap();
+        session
+    }
+
+    fn submit(server: &str, player: &str, sessions: Vec<SessionRecord>) -> usize {
+        let request = Request::Submit {
+            player: player.to_string(),
+            sessions,
+        };
+        match team::send(server, &request).unwrap() {
+            Response::Submitted { added } => added,
+            response => panic!("unexpected answer {response:?}"),
+        }
+    }
+
+    fn standings(server: &str, ranking: Ranking) -> Vec<(String, Vec<(String, u32)>)> {
+        team::leaderboard(server, "server-test", ranking)
+            .unwrap()
+            .into_iter()
+            .map(|board| {
+                let entries = board
+                    .entries
+                    .into_iter()
+                    .map(|entry| (entry.player, entry.session.score))
+                    .collect();
+                (board.mode, entries)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn serves_submitted_sessions_ranked() {
+        storage::use_test_dir();
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let server = listener.local_addr().unwrap().to_string();
+        thread::spawn(move || listen(listener, Vec::new()));
+
+        let ann = vec![session("standard", 300, 0), session("standard", 250, 1), session("custom", 400, 2)];
+        assert_eq!(submit(&server, "ann", ann.clone()), 3);
+        // Sessions the server has are skipped, given up ones are never kept
+        assert_eq!(submit(&server, "ann", ann), 0);
+        assert_eq!(submit(&server, "bob", vec![session("standard", 280, 3), session("standard", 200, 4).aborted()]), 1);
+        assert_eq!(submit(&server, "cid", vec![session("standard", 320, 5)]), 1);
+
+        let pairs = |entries: &[(&str, u32)]| entries.iter().map(|(player, score)| (player.to_string(), *score)).collect();
+        assert_eq!(
+            standings(&server, Ranking::LowerIsBetter),
+            vec![
+                ("standard".to_string(), pairs(&[("ann", 250), ("bob", 280), ("cid", 320)])),
+                ("custom".to_string(), pairs(&[("ann", 400)])),
+            ]
+        );
+        assert_eq!(
+            standings(&server, Ranking::HigherIsBetter),
+            vec![
+                ("standard".to_string(), pairs(&[("cid", 320), ("ann", 300), ("bob", 280)])),
+                ("custom".to_string(), pairs(&[("ann", 400)])),
+            ]
+        );
+    }
+}
@@ -0,0 +1,114 @@
+use crate::games::settings::Setting;
+use crate::keymap::{Action, KeyMap};
+use crossterm::event::KeyEvent;
+use ratatui::layout::{Constraint, Direction, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Wrap};
+use ratatui::Frame;
+
+/// Options of a game, edited from the menu before launching it.
+///
+/// The menu hands it every key and gives the values back to the game once saved,
+/// the game has the last word on whether they work together.
+pub struct SettingsForm {
+    title: String,
+    settings: Vec<Setting>,
+    selected: usize,
+    error: Option<String>, // Why the last save was refused
+}
+
+impl SettingsForm {
+    pub fn new<T: Into<String>>(title: T, settings: Vec<Setting>) -> Self {
+        Self {
+            title: title.into(),
+            settings,
+            selected: 0,
+            error: None,
+        }
+    }
+
+    pub fn settings(&self) -> &[Setting] {
+        &self.settings
+    }
+
+    /// Shown until the next change, after the game refused the values.
+    pub fn set_error(&mut self, error: String) {
+        self.error = Some(error);
+    }
+
+    /// Returns `Some(true)` to save, `Some(false)` to leave without saving, `None` while editing.
+    ///
+    /// Values that are out of bounds can't be saved, stepping keeps them within bounds so
+    /// this only happens when a game hands out a value it doesn't accept itself.
+    pub fn handle_key(&mut self, key_event: KeyEvent, keys: &KeyMap) -> Option<bool> {
+        let among = [Action::Up, Action::Down, Action::Left, Action::Right, Action::Confirm, Action::Back];
+        match keys.action(&key_event, &among)? {
+            Action::Up => self.selected = self.selected.saturating_sub(1),
+            Action::Down => self.selected = (self.selected + 1).min(self.settings.len().saturating_sub(1)),
+            Action::Left | Action::Right => {
+                if let Some(setting) = self.settings.get_mut(self.selected) {
+                    setting.step(keys.is(Action::Right, &key_event));
+                    self.error = None;
+                }
+            }
+            Action::Confirm => match self.settings.iter().find(|setting| !setting.is_valid()) {
+                Some(setting) => self.error = Some(format!("{} is out of range", setting.label)),
+                None => return Some(true),
+            },
+            _ => return Some(false),
+        }
+        None
+    }
+
+    pub fn render(&self, frame: &mut Frame, area: Rect) {
+        let block = Block::default()
+            .title(format!(" ⚙ {} ", self.title))
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::White));
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+
+        let parts = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(3), Constraint::Length(3)])
+            .split(inner);
+
+        if self.settings.is_empty() {
+            frame.render_widget(Paragraph::new("This game has nothing to set"), parts[0]);
+        } else {
+            let width = self.settings.iter().map(|setting| setting.label.chars().count()).max().unwrap_or(0) + 2;
+            let items: Vec<ListItem> = self
+                .settings
+                .iter()
+                .map(|setting| {
+                    ListItem::new(Line::from(vec![
+                        Span::styled(
+                            format!("{:<width$}", setting.label),
+                            Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD),
+                        ),
+                        Span::raw(format!("◀ {} ▶", setting.display())),
+                    ]))
+                })
+                .collect();
+
+            let list = List::new(items)
+                .highlight_style(
+                    Style::default()
+                        .bg(Color::Blue)
+                        .fg(Color::White)
+                        .add_modifier(Modifier::BOLD),
+                )
+                .highlight_symbol(">> ");
+            let mut state = ListState::default();
+            state.select(Some(self.selected));
+            frame.render_stateful_widget(list, parts[0], &mut state);
+        }
+
+        if let Some(error) = &self.error {
+            let error = Paragraph::new(Line::styled(error.clone(), Style::default().fg(Color::Red)))
+                .wrap(Wrap { trim: true });
+            frame.render_widget(error, parts[1]);
+        }
+    }
+}
@@ -0,0 +1,109 @@
+use crate::storage;
+use hmac::{Hmac, Mac};
+use rand::rngs::StdRng;
+use rand::{RngCore, SeedableRng};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use sha2::Sha256;
+use std::io;
+
+/// Secret of the profile results are signed with.
+const KEY_FILE: &str = "signing_key.json";
+/// Field of a signed JSON object holding its signature.
+const FIELD: &str = "signature";
+
+/// HMAC-SHA256 key of a profile, kept next to the results it signs.
+///
+/// It doesn't stop someone who reads the code, only makes a hand edit of a results file show.
+#[derive(Default, Serialize, Deserialize)]
+pub struct Key {
+    key: String,
+}
+
+impl Key {
+    /// The profile's key, `None` until something was signed.
+    pub fn load() -> Option<Self> {
+        let key: Key = storage::load(KEY_FILE);
+        (!key.key.is_empty()).then_some(key)
+    }
+
+    pub fn generate() -> Self {
+        let mut bytes = [0; 32];
+        StdRng::from_os_rng().fill_bytes(&mut bytes);
+        Self { key: hex(&bytes) }
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        storage::save(KEY_FILE, self)
+    }
+
+    /// The object with its signature set, over every other field.
+    pub fn sign(&self, value: Value) -> Value {
+        let Value::Object(mut fields) = value else {
+            return value;
+        };
+        fields.remove(FIELD);
+        let signature = self.signature(&fields);
+        fields.insert(FIELD.to_string(), Value::String(signature));
+        Value::Object(fields)
+    }
+
+    /// Whether the object carries the signature of its other fields, as `sign` left it.
+    pub fn verify(&self, value: &Value) -> bool {
+        let Some(mut fields) = value.as_object().cloned() else {
+            return false;
+        };
+        match fields.remove(FIELD) {
+            Some(Value::String(signature)) => signature == self.signature(&fields),
+            _ => false,
+        }
+    }
+
+    fn signature(&self, fields: &Map<String, Value>) -> String {
+        // Objects are written with sorted keys, so reading a line back gives the text that was signed
+        let text = serde_json::to_string(fields).unwrap_or_default();
+        let mut mac = Hmac::<Sha256>::new_from_slice(self.key.as_bytes()).expect("HMAC takes keys of any length");
+        mac.update(text.as_bytes());
+        hex(&mac.finalize().into_bytes())
+    }
+}
+
+/// Whether the object carries a signature, whatever key made it.
+pub fn is_signed(value: &Value) -> bool {
+    value.get(FIELD).is_some()
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    // Signatures are the hex HMAC-SHA256 of the compact JSON of the other fields, files signed
+    // by earlier versions must keep verifying
+    #[test]
+    fn signs_compact_json_with_hmac_sha256() {
+        let key = Key {
+            key: "0123456789abcdef".to_string(),
+        };
+        let signed = key.sign(json!({ "score": 250, "game": "reaction" }));
+        assert_eq!(signed[FIELD], "fa312a720b74c2fd2e3344fb7deeb8024caf68e2468b435159618f7124164f0b");
+    }
+
+    #[test]
+    fn edits_break_the_signature() {
+        let key = Key::generate();
+        let signed = key.sign(json!({ "game": "reaction", "score": 250 }));
+        assert!(is_signed(&signed));
+        assert!(key.verify(&signed));
+
+        let mut edited = signed.clone();
+        edited["score"] = json!(150);
+        assert!(!key.verify(&edited));
+        assert!(!Key::generate().verify(&signed));
+        assert!(!key.verify(&json!({ "game": "reaction", "score": 250 })));
+    }
+}
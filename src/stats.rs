@@ -0,0 +1,275 @@
+use crate::history::{Ranking, SessionRecord};
+use chrono::{DateTime, Local, NaiveDate, Timelike};
+use std::cmp::Reverse;
+
+/// Sizes of the rolling averages, in sessions.
+pub const ROLLING_WINDOWS: [usize; 2] = [5, 20];
+/// Percentiles of the scores shown by the dashboard.
+pub const PERCENTILES: [u8; 5] = [10, 25, 50, 75, 90];
+
+/// Part of the day a session was played in, by local time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeOfDay {
+    Night,
+    Morning,
+    Afternoon,
+    Evening,
+}
+
+impl TimeOfDay {
+    pub const ALL: [TimeOfDay; 4] = [TimeOfDay::Morning, TimeOfDay::Afternoon, TimeOfDay::Evening, TimeOfDay::Night];
+
+    pub fn of(at: DateTime<Local>) -> Self {
+        match at.hour() {
+            6..12 => TimeOfDay::Morning,
+            12..18 => TimeOfDay::Afternoon,
+            18..24 => TimeOfDay::Evening,
+            _ => TimeOfDay::Night,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            TimeOfDay::Night => "Night (0–6 h)",
+            TimeOfDay::Morning => "Morning (6–12 h)",
+            TimeOfDay::Afternoon => "Afternoon (12–18 h)",
+            TimeOfDay::Evening => "Evening (18–24 h)",
+        }
+    }
+}
+
+/// How the sessions played in one part of the day went.
+pub struct TimeOfDayStats {
+    pub period: TimeOfDay,
+    pub sessions: usize,
+    /// `None` when nothing was played then.
+    pub average: Option<f64>,
+}
+
+/// Days in a row with at least one finished session.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Streaks {
+    /// Still going if the last session was today or yesterday, 0 otherwise.
+    pub current: u32,
+    pub longest: u32,
+}
+
+/// Everything the dashboard shows about one mode of a game.
+pub struct ModeStats {
+    pub sessions: usize,
+    pub best: u32,
+    pub average: f64,
+    /// Scores over time: days since the first session, and the score.
+    pub points: Vec<(f64, f64)>,
+    /// For each of `ROLLING_WINDOWS`, the average of the last sessions at each session.
+    pub rolling: Vec<(usize, Vec<(f64, f64)>)>,
+    /// For each of `PERCENTILES`, the score below which that share of the sessions fall.
+    pub percentiles: Vec<(u8, f64)>,
+    pub streaks: Streaks,
+    pub time_of_day: Vec<TimeOfDayStats>,
+}
+
+/// Modes a game was played to the end in, most recently played first.
+pub fn modes(sessions: &[SessionRecord], game: &str) -> Vec<String> {
+    let mut finished: Vec<&SessionRecord> = finished(sessions, game).collect();
+    finished.sort_by_key(|session| Reverse(session.played_at));
+
+    let mut modes: Vec<String> = Vec::new();
+    for session in finished {
+        if !modes.contains(&session.mode) {
+            modes.push(session.mode.clone());
+        }
+    }
+    modes
+}
+
+/// Aggregates the finished sessions of a game mode, `None` if there are none.
+///
+/// `today` decides whether the current streak is still going.
+pub fn mode_stats(
+    sessions: &[SessionRecord],
+    game: &str,
+    mode: &str,
+    ranking: Ranking,
+    today: NaiveDate,
+) -> Option<ModeStats> {
+    let mut played: Vec<&SessionRecord> = finished(sessions, game)
+        .filter(|session| session.mode == mode)
+        .collect();
+    played.sort_by_key(|session| session.played_at);
+
+    let first = played.first()?.played_at;
+    let days = |at: DateTime<Local>| (at - first).num_seconds() as f64 / 86_400.0;
+    let scores: Vec<f64> = played.iter().map(|session| session.score as f64).collect();
+
+    let points = played
+        .iter()
+        .map(|session| (days(session.played_at), session.score as f64))
+        .collect();
+    let rolling = ROLLING_WINDOWS
+        .iter()
+        .map(|window| {
+            let averages = rolling_average(&scores, *window)
+                .into_iter()
+                .zip(&played)
+                .map(|(average, session)| (days(session.played_at), average))
+                .collect();
+            (*window, averages)
+        })
+        .collect();
+
+    let mut sorted: Vec<u32> = played.iter().map(|session| session.score).collect();
+    sorted.sort_unstable();
+    let percentiles = PERCENTILES
+        .iter()
+        .map(|p| (*p, percentile(&sorted, *p)))
+        .collect();
+
+    let best = played
+        .iter()
+        .map(|session| session.score)
+        .min_by(|a, b| ranking.compare(*a, *b))?;
+
+    let time_of_day = TimeOfDay::ALL
+        .iter()
+        .map(|period| {
+            let scores: Vec<f64> = played
+                .iter()
+                .filter(|session| TimeOfDay::of(session.played_at) == *period)
+                .map(|session| session.score as f64)
+                .collect();
+            TimeOfDayStats {
+                period: *period,
+                sessions: scores.len(),
+                average: (!scores.is_empty()).then(|| mean(&scores)),
+            }
+        })
+        .collect();
+
+    Some(ModeStats {
+        sessions: played.len(),
+        best,
+        average: mean(&scores),
+        points,
+        rolling,
+        percentiles,
+        // Days played on another machine can't be vouched for, imported sessions don't make a streak
+        streaks: streaks(
+            played.iter().filter(|session| !session.imported).map(|session| session.played_at.date_naive()),
+            today,
+        ),
+        time_of_day,
+    })
+}
+
+fn finished<'a>(sessions: &'a [SessionRecord], game: &'a str) -> impl Iterator<Item = &'a SessionRecord> {
+    sessions
+        .iter()
+        .filter(move |session| !session.aborted && session.game == game)
+}
+
+fn mean(values: &[f64]) -> f64 {
+    if values.is_empty() {
+        return 0.0;
+    }
+    values.iter().sum::<f64>() / values.len() as f64
+}
+
+/// Average of the last `window` values at each position, shorter at the start.
+pub fn rolling_average(values: &[f64], window: usize) -> Vec<f64> {
+    (0..values.len())
+        .map(|i| mean(&values[(i + 1).saturating_sub(window)..=i]))
+        .collect()
+}
+
+/// Linearly interpolated percentile of sorted scores, `p` from 0 to 100.
+pub fn percentile(sorted: &[u32], p: u8) -> f64 {
+    let Some(last) = sorted.len().checked_sub(1) else {
+        return 0.0;
+    };
+    let rank = last as f64 * p.min(100) as f64 / 100.0;
+    let below = sorted[rank.floor() as usize] as f64;
+    let above = sorted[rank.ceil() as usize] as f64;
+    below + (above - below) * rank.fract()
+}
+
+/// Streaks of consecutive days in the given dates, which may repeat and come in any order.
+pub fn streaks(dates: impl Iterator<Item = NaiveDate>, today: NaiveDate) -> Streaks {
+    let mut days: Vec<NaiveDate> = dates.collect();
+    days.sort_unstable();
+    days.dedup();
+
+    let mut longest = 0;
+    let mut run = 0;
+    let mut previous: Option<NaiveDate> = None;
+    for day in &days {
+        run = match previous {
+            Some(previous) if previous.succ_opt() == Some(*day) => run + 1,
+            _ => 1,
+        };
+        longest = longest.max(run);
+        previous = Some(*day);
+    }
+
+    // A streak isn't broken until a whole day goes by without playing
+    let still_going = previous.is_some_and(|last| last == today || last.succ_opt() == Some(today));
+    Streaks {
+        current: if still_going { run } else { 0 },
+        longest,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn day(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, 3, day).unwrap()
+    }
+
+    fn streaks_on(days: &[u32], today: u32) -> Streaks {
+        streaks(days.iter().map(|d| day(*d)), day(today))
+    }
+
+    #[test]
+    fn percentiles_interpolate_between_scores() {
+        let sorted = [100, 200, 300, 400, 500];
+        assert_eq!(percentile(&sorted, 0), 100.0);
+        assert_eq!(percentile(&sorted, 50), 300.0);
+        assert_eq!(percentile(&sorted, 90), 460.0);
+        assert_eq!(percentile(&sorted, 100), 500.0);
+        // Anything past 100 is the best score, and no scores at all give 0
+        assert_eq!(percentile(&sorted, 255), 500.0);
+        assert_eq!(percentile(&[250], 75), 250.0);
+        assert_eq!(percentile(&[], 50), 0.0);
+    }
+
+    #[test]
+    fn rolling_average_is_shorter_at_the_start() {
+        let values = [2.0, 4.0, 6.0, 8.0];
+        assert_eq!(rolling_average(&values, 2), vec![2.0, 3.0, 5.0, 7.0]);
+        assert_eq!(rolling_average(&values, 10), vec![2.0, 3.0, 4.0, 5.0]);
+        assert_eq!(rolling_average(&values, 1), values.to_vec());
+        assert!(rolling_average(&[], 3).is_empty());
+    }
+
+    #[test]
+    fn streaks_count_days_in_a_row() {
+        assert_eq!(streaks_on(&[1, 2, 3, 5, 6], 6), Streaks { current: 2, longest: 3 });
+        // Order doesn't matter, several sessions on a day count once
+        assert_eq!(streaks_on(&[6, 2, 2, 1, 6, 3, 5, 3], 6), Streaks { current: 2, longest: 3 });
+        assert_eq!(streaks_on(&[], 6), Streaks::default());
+    }
+
+    #[test]
+    fn streaks_survive_until_a_day_is_missed() {
+        // Played yesterday, today can still extend it
+        assert_eq!(streaks_on(&[4, 5], 6), Streaks { current: 2, longest: 2 });
+        assert_eq!(streaks_on(&[4, 5, 6], 6), Streaks { current: 3, longest: 3 });
+        // A whole day without playing
+        assert_eq!(streaks_on(&[4, 5], 7), Streaks { current: 0, longest: 2 });
+        // Across the end of a month
+        let days = [NaiveDate::from_ymd_opt(2026, 2, 28).unwrap(), day(1)];
+        assert_eq!(streaks(days.into_iter(), day(2)), Streaks { current: 2, longest: 2 });
+    }
+}
@@ -0,0 +1,297 @@
+use crate::export::{self, Format};
+use crate::games::Game;
+use crate::history::{self, Ranking, SessionRecord};
+use crate::keymap::{Action, KeyMap};
+use crate::stats::{self, ModeStats};
+use chrono::Local;
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::layout::{Alignment, Constraint, Direction, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::symbols::Marker;
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Axis, Block, Borders, Chart, Dataset, GraphType, Paragraph};
+use ratatui::Frame;
+
+/// What the dashboard needs to know about a game.
+pub struct StatsGame {
+    pub id: String,
+    pub name: String,
+    pub ranking: Ranking,
+    pub unit: String,
+}
+
+/// Progress dashboard, run by the engine like a game.
+///
+/// Shows one mode of one game at a time: scores over time with rolling averages,
+/// percentiles, daily streaks and how the time of day plays into the scores.
+pub struct StatsScreen {
+    games: Vec<StatsGame>,
+    sessions: Vec<SessionRecord>,
+    game_index: usize,
+    modes: Vec<String>,       // Modes of the selected game, most recently played first
+    mode_index: usize,
+    stats: Option<ModeStats>, // Aggregates of the selected mode
+    message: String,          // Outcome of the last export
+    edited: usize,            // Sessions left out because the history was edited by hand
+    quit: bool,
+}
+
+impl StatsScreen {
+    pub fn new(games: Vec<StatsGame>) -> Self {
+        let mut screen = Self {
+            games,
+            sessions: Vec::new(),
+            game_index: 0,
+            modes: Vec::new(),
+            mode_index: 0,
+            stats: None,
+            message: String::new(),
+            edited: 0,
+            quit: false,
+        };
+        screen.reload();
+        screen
+    }
+
+    /// Reads the history again, sessions may have been played since last time.
+    fn reload(&mut self) {
+        self.sessions = history::sessions();
+        self.edited = history::edited();
+        self.select_game(self.game_index);
+    }
+
+    fn select_game(&mut self, index: usize) {
+        self.game_index = index;
+        self.modes = match self.games.get(index) {
+            Some(game) => stats::modes(&self.sessions, &game.id),
+            None => Vec::new(),
+        };
+        self.select_mode(0);
+    }
+
+    fn select_mode(&mut self, index: usize) {
+        self.mode_index = index;
+        self.stats = match (self.games.get(self.game_index), self.modes.get(index)) {
+            (Some(game), Some(mode)) => {
+                stats::mode_stats(&self.sessions, &game.id, mode, game.ranking, Local::now().date_naive())
+            }
+            _ => None,
+        };
+    }
+
+    /// Writes every session of the selected game, all modes, to a CSV file in the profile.
+    fn export(&mut self) {
+        let Some(game) = self.games.get(self.game_index) else {
+            return;
+        };
+        let sessions = export::select(self.sessions.clone(), Some(&game.id), None);
+        self.message = match export::export_to_profile_dir(&sessions, &game.id, Format::Csv) {
+            Ok(path) => format!("{} sessions exported to {}", sessions.len(), path.display()),
+            Err(err) => format!("Export failed: {err}"),
+        };
+    }
+
+    fn render_header(&self, frame: &mut Frame, area: Rect, game: &StatsGame) {
+        let bold = Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD);
+        let mode = match self.modes.get(self.mode_index) {
+            Some(mode) => format!("Mode: ▲ {} ▼", mode.replace('_', " ")),
+            None => "Not played to the end yet".to_string(),
+        };
+        let lines = vec![
+            Line::from(Span::styled(format!("◀ {} ▶", game.name), bold)),
+            Line::from(mode),
+            if self.message.is_empty() && self.edited > 0 {
+                Line::styled(
+                    format!("⚠ {} edited sessions of the history are left out", self.edited),
+                    Style::default().fg(Color::Red),
+                )
+            } else {
+                Line::styled(self.message.clone(), Style::default().fg(Color::Gray))
+            },
+        ];
+        frame.render_widget(Paragraph::new(lines).alignment(Alignment::Center), area);
+    }
+
+    fn render_chart(&self, frame: &mut Frame, area: Rect, stats: &ModeStats, game: &StatsGame) {
+        let colors = [Color::Yellow, Color::Magenta];
+        let mut datasets = vec![Dataset::default()
+            .name(format!("Score ({})", game.unit))
+            .marker(Marker::Braille)
+            .graph_type(GraphType::Scatter)
+            .style(Style::default().fg(Color::Cyan))
+            .data(&stats.points)];
+        for ((window, averages), color) in stats.rolling.iter().zip(colors) {
+            datasets.push(
+                Dataset::default()
+                    .name(format!("Average of {window}"))
+                    .marker(Marker::Braille)
+                    .graph_type(GraphType::Line)
+                    .style(Style::default().fg(color))
+                    .data(averages),
+            );
+        }
+
+        let last_day = stats.points.iter().map(|(x, _)| *x).fold(0.0, f64::max).max(1.0);
+        let (low, high) = stats
+            .points
+            .iter()
+            .fold((f64::MAX, f64::MIN), |(low, high), (_, y)| (low.min(*y), high.max(*y)));
+        // Some room above and below so the extremes don't sit on the axes
+        let margin = ((high - low) * 0.1).max(1.0);
+        let (low, high) = ((low - margin).max(0.0), high + margin);
+
+        let chart = Chart::new(datasets)
+            .block(Block::default().title(" Scores over time ").borders(Borders::ALL))
+            .x_axis(
+                Axis::default()
+                    .title("days")
+                    .style(Style::default().fg(Color::Gray))
+                    .bounds([0.0, last_day])
+                    .labels(["0".to_string(), format!("{:.0}", last_day / 2.0), format!("{last_day:.0}")]),
+            )
+            .y_axis(
+                Axis::default()
+                    .title(game.unit.clone())
+                    .style(Style::default().fg(Color::Gray))
+                    .bounds([low, high])
+                    .labels([format!("{low:.0}"), format!("{:.0}", (low + high) / 2.0), format!("{high:.0}")]),
+            );
+        frame.render_widget(chart, area);
+    }
+
+    fn render_figures(&self, frame: &mut Frame, area: Rect, stats: &ModeStats, game: &StatsGame) {
+        let columns = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(30), Constraint::Percentage(25), Constraint::Percentage(45)])
+            .split(area);
+        let unit = &game.unit;
+
+        let mut percentiles = vec![
+            Line::from(format!("Sessions: {}", stats.sessions)),
+            Line::from(format!("Best: {} {unit}", stats.best)),
+            Line::from(format!("Average: {:.0} {unit}", stats.average)),
+        ];
+        percentiles.extend(
+            stats
+                .percentiles
+                .iter()
+                .map(|(p, value)| Line::from(format!("p{p}: {value:.0} {unit}"))),
+        );
+        frame.render_widget(
+            Paragraph::new(percentiles).block(Block::default().title(" Scores ").borders(Borders::ALL)),
+            columns[0],
+        );
+
+        let streaks = vec![
+            Line::from(format!("Current: {} days", stats.streaks.current)),
+            Line::from(format!("Longest: {} days", stats.streaks.longest)),
+        ];
+        frame.render_widget(
+            Paragraph::new(streaks).block(Block::default().title(" Daily streak ").borders(Borders::ALL)),
+            columns[1],
+        );
+
+        let periods: Vec<Line> = stats
+            .time_of_day
+            .iter()
+            .map(|period| match period.average {
+                Some(average) => Line::from(format!(
+                    "{:<20}{:>4} runs  avg {average:.0} {unit}",
+                    period.period.name(),
+                    period.sessions
+                )),
+                None => Line::styled(format!("{:<20}   –", period.period.name()), Style::default().fg(Color::Gray)),
+            })
+            .collect();
+        frame.render_widget(
+            Paragraph::new(periods).block(Block::default().title(" Time of day ").borders(Borders::ALL)),
+            columns[2],
+        );
+    }
+}
+
+impl Game for StatsScreen {
+    fn name(&self) -> &str {
+        "📈 Stats"
+    }
+
+    fn description(&self) -> &str {
+        "How your scores evolve over time"
+    }
+
+    fn id(&self) -> &str {
+        "stats"
+    }
+
+    fn help(&self) -> &str {
+        "Scores of every finished run, per game and mode, with rolling averages.\n\
+         Percentiles tell how your runs spread, the streak counts days in a row\n\
+         with at least one finished run, and the time of day shows when you play best.\n\
+         \n\
+         left/right: game • up/down: mode • e: export the game's sessions to CSV\n\
+         Back: return to the menu"
+    }
+
+    fn handle_input(&mut self, key_event: KeyEvent, keys: &KeyMap) {
+        let games = self.games.len().max(1);
+        let modes = self.modes.len().max(1);
+        let among = [Action::Left, Action::Right, Action::Up, Action::Down, Action::Back];
+        match keys.action(&key_event, &among) {
+            Some(Action::Left) => self.select_game((self.game_index + games - 1) % games),
+            Some(Action::Right) => self.select_game((self.game_index + 1) % games),
+            Some(Action::Up) => self.select_mode((self.mode_index + modes - 1) % modes),
+            Some(Action::Down) => self.select_mode((self.mode_index + 1) % modes),
+            Some(_) => self.quit = true,
+            None if key_event.code == KeyCode::Char('e') => self.export(),
+            None => {}
+        }
+    }
+
+    fn render(&self, frame: &mut Frame) {
+        let layout = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(4),
+                Constraint::Min(10),
+                Constraint::Length(10),
+                Constraint::Length(1),
+            ])
+            .split(frame.area());
+
+        let Some(game) = self.games.get(self.game_index) else {
+            return;
+        };
+        self.render_header(frame, layout[0], game);
+
+        match &self.stats {
+            Some(stats) => {
+                self.render_chart(frame, layout[1], stats, game);
+                self.render_figures(frame, layout[2], stats, game);
+            }
+            None => frame.render_widget(
+                Paragraph::new("Finish a run to see your progress here")
+                    .alignment(Alignment::Center)
+                    .block(Block::default().borders(Borders::ALL)),
+                layout[1],
+            ),
+        }
+
+        let hint = Paragraph::new("left/right: game • up/down: mode • e: export to CSV • Back: return to the menu")
+            .style(Style::default().fg(Color::Gray))
+            .alignment(Alignment::Center);
+        frame.render_widget(hint, layout[3]);
+    }
+
+    fn wants_exit(&self) -> bool {
+        self.quit
+    }
+
+    fn restart(&mut self) {
+        self.reload();
+    }
+
+    fn on_exit(&mut self) {
+        self.message.clear();
+        self.quit = false;
+    }
+}
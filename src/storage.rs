@@ -0,0 +1,171 @@
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use std::fs;
+use std::io;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Folder of the data directory the profiles' own folders are in.
+pub const PROFILES_DIR: &str = "profiles";
+
+/// Folder of the player's profile within `PROFILES_DIR`, `None` until one is picked.
+static PROFILE: RwLock<Option<String>> = RwLock::new(None);
+
+/// Set while a replay plays, the games it runs must not change what is saved.
+static READ_ONLY: AtomicBool = AtomicBool::new(false);
+
+/// Directory where settings and results are kept.
+///
+/// `RATAPLAY_HOME` overrides the platform data directory, which is handy to keep
+/// separate setups or to try things without touching real data.
+pub fn data_dir() -> PathBuf {
+    std::env::var_os("RATAPLAY_HOME")
+        .map(PathBuf::from)
+        .or_else(|| dirs::data_dir().map(|dir| dir.join("rataplay")))
+        .unwrap_or_else(|| PathBuf::from(".rataplay"))
+}
+
+/// Makes the files below belong to a profile, `folder` is its folder in `PROFILES_DIR`.
+pub fn set_profile(folder: Option<String>) {
+    *PROFILE.write().unwrap_or_else(|poisoned| poisoned.into_inner()) = folder;
+}
+
+/// Folder of the current profile, `None` before one is picked.
+pub fn profile_folder() -> Option<String> {
+    PROFILE.read().unwrap_or_else(|poisoned| poisoned.into_inner()).clone()
+}
+
+/// Where the files of the current player are kept: scores, settings and key bindings.
+///
+/// Before a profile is picked that's the data directory itself.
+pub fn profile_dir() -> PathBuf {
+    let profile = PROFILE.read().unwrap_or_else(|poisoned| poisoned.into_inner());
+    match profile.as_deref() {
+        Some(folder) => data_dir().join(PROFILES_DIR).join(folder),
+        None => data_dir(),
+    }
+}
+
+/// Makes every write below a no-op that reports success, or writes again.
+pub fn set_read_only(read_only: bool) {
+    READ_ONLY.store(read_only, Ordering::Relaxed);
+}
+
+/// Moves the data directory to a fresh folder of this test process, so tests never see real data.
+#[cfg(test)]
+pub fn use_test_dir() {
+    static ONCE: std::sync::Once = std::sync::Once::new();
+    ONCE.call_once(|| {
+        let dir = std::env::temp_dir().join(format!("rataplay-test-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        std::env::set_var("RATAPLAY_HOME", dir);
+    });
+}
+
+/// Reads a JSON file of the current profile.
+///
+/// A missing or unreadable file gives the default value, so a broken file never stops a game from starting.
+pub fn load<T: DeserializeOwned + Default>(name: &str) -> T {
+    load_from(&profile_dir(), name)
+}
+
+/// Reads a JSON file shared by every profile, from the data directory.
+pub fn load_shared<T: DeserializeOwned + Default>(name: &str) -> T {
+    load_from(&data_dir(), name)
+}
+
+fn load_from<T: DeserializeOwned + Default>(dir: &Path, name: &str) -> T {
+    fs::read_to_string(dir.join(name))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+/// Writes a value as JSON into the current profile.
+pub fn save<T: Serialize>(name: &str, value: &T) -> io::Result<()> {
+    save_into(&profile_dir(), name, value)
+}
+
+/// Writes a value as JSON into the data directory, for every profile to see.
+pub fn save_shared<T: Serialize>(name: &str, value: &T) -> io::Result<()> {
+    save_into(&data_dir(), name, value)
+}
+
+fn save_into<T: Serialize>(dir: &Path, name: &str, value: &T) -> io::Result<()> {
+    let content = serde_json::to_string_pretty(value).map_err(io::Error::other)?;
+    replace_into(dir, name, content)
+}
+
+/// Replaces a file of the current profile with one JSON line per value.
+pub fn save_lines<T: Serialize>(name: &str, values: &[T]) -> io::Result<()> {
+    let mut content = String::new();
+    for value in values {
+        content.push_str(&serde_json::to_string(value).map_err(io::Error::other)?);
+        content.push('\n');
+    }
+    replace_into(&profile_dir(), name, content)
+}
+
+/// The file is written next to its destination first and then renamed, so a crash
+/// mid-write leaves the previous version intact.
+fn replace_into(dir: &Path, name: &str, content: String) -> io::Result<()> {
+    if READ_ONLY.load(Ordering::Relaxed) {
+        return Ok(());
+    }
+    fs::create_dir_all(dir)?;
+
+    let tmp = dir.join(format!("{name}.tmp"));
+    fs::write(&tmp, content)?;
+    fs::rename(tmp, dir.join(name))
+}
+
+/// Appends a value as one JSON line to a file of the current profile.
+pub fn append_line<T: Serialize>(name: &str, value: &T) -> io::Result<()> {
+    append_line_into(&profile_dir(), name, value)
+}
+
+/// Appends a value as one JSON line to a file of the data directory, shared by every profile.
+pub fn append_shared_line<T: Serialize>(name: &str, value: &T) -> io::Result<()> {
+    append_line_into(&data_dir(), name, value)
+}
+
+fn append_line_into<T: Serialize>(dir: &Path, name: &str, value: &T) -> io::Result<()> {
+    if READ_ONLY.load(Ordering::Relaxed) {
+        return Ok(());
+    }
+    fs::create_dir_all(dir)?;
+
+    let mut line = serde_json::to_string(value).map_err(io::Error::other)?;
+    line.push('\n');
+
+    let mut file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(dir.join(name))?;
+    file.write_all(line.as_bytes())
+}
+
+/// Reads every JSON line of a file of the current profile.
+///
+/// Lines that can't be parsed are skipped, a half-written last line only loses that entry.
+pub fn load_lines<T: DeserializeOwned>(name: &str) -> Vec<T> {
+    load_lines_from(&profile_dir(), name)
+}
+
+/// Reads every JSON line of a file of the data directory.
+pub fn load_shared_lines<T: DeserializeOwned>(name: &str) -> Vec<T> {
+    load_lines_from(&data_dir(), name)
+}
+
+fn load_lines_from<T: DeserializeOwned>(dir: &Path, name: &str) -> Vec<T> {
+    fs::read_to_string(dir.join(name))
+        .map(|content| {
+            content
+                .lines()
+                .filter_map(|line| serde_json::from_str(line).ok())
+                .collect()
+        })
+        .unwrap_or_default()
+}
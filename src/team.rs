@@ -0,0 +1,179 @@
+use crate::history::{self, Ranking, SessionId, SessionRecord};
+use crate::profile;
+use crate::storage;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+
+/// Port of the leaderboard server when the address doesn't say.
+pub const DEFAULT_PORT: u16 = 7878;
+/// Largest request or response accepted, one line of JSON.
+pub const MAX_MESSAGE_BYTES: u64 = 4 * 1024 * 1024;
+/// Where the player's team server is, per profile.
+const TEAM_FILE: &str = "team.json";
+/// How many players each mode of a team leaderboard shows.
+const LEADERBOARD_SIZE: usize = 10;
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(2);
+const IO_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Only one sync at a time, so two finished runs in a row don't send the same sessions twice.
+static SYNCING: Mutex<()> = Mutex::new(());
+
+/// Team server of the player, saved in the profile.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TeamConfig {
+    /// `host` or `host:port` of the server, `None` to keep scores to oneself.
+    pub server: Option<String>,
+    /// Sessions the server received, any other finished one is sent at the next sync,
+    /// however long ago it was played.
+    pub synced: BTreeSet<SessionId>,
+}
+
+impl TeamConfig {
+    pub fn load() -> Self {
+        storage::load(TEAM_FILE)
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        storage::save(TEAM_FILE, self)
+    }
+}
+
+/// What a client asks the server, one JSON line per connection.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Request {
+    /// Finished sessions of a player, the ones the server already has are ignored.
+    Submit { player: String, sessions: Vec<SessionRecord> },
+    /// Best session of each player, for every mode of a game.
+    Leaderboard { game: String, ranking: Ranking, limit: usize },
+}
+
+/// The server's answer, one JSON line.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Response {
+    Submitted { added: usize },
+    Leaderboard { modes: Vec<ModeBoard> },
+    Error { message: String },
+}
+
+/// A session as the server keeps it, with who played it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TeamEntry {
+    pub player: String,
+    pub session: SessionRecord,
+}
+
+/// Ranking of one mode of a game, best first.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModeBoard {
+    pub mode: String,
+    pub entries: Vec<TeamEntry>,
+}
+
+/// Sends one request to the server and waits for its answer.
+pub fn send(server: &str, request: &Request) -> io::Result<Response> {
+    let mut stream = connect(server)?;
+    stream.set_read_timeout(Some(IO_TIMEOUT))?;
+    stream.set_write_timeout(Some(IO_TIMEOUT))?;
+
+    let mut line = serde_json::to_string(request).map_err(io::Error::from)?;
+    line.push('\n');
+    stream.write_all(line.as_bytes())?;
+
+    let mut answer = String::new();
+    BufReader::new(stream.take(MAX_MESSAGE_BYTES)).read_line(&mut answer)?;
+    match serde_json::from_str(&answer).map_err(io::Error::from)? {
+        Response::Error { message } => Err(io::Error::other(message)),
+        response => Ok(response),
+    }
+}
+
+/// `host` alone means the default port.
+fn connect(server: &str) -> io::Result<TcpStream> {
+    let server = server.trim();
+    let address = if server.contains(':') {
+        server.to_string()
+    } else {
+        format!("{server}:{DEFAULT_PORT}")
+    };
+
+    let mut last_error = io::Error::new(io::ErrorKind::NotFound, format!("{server} doesn't resolve"));
+    for address in address.to_socket_addrs()? {
+        match TcpStream::connect_timeout(&address, CONNECT_TIMEOUT) {
+            Ok(stream) => return Ok(stream),
+            Err(err) => last_error = err,
+        }
+    }
+    Err(last_error)
+}
+
+/// Sends the finished sessions the server hasn't received yet, returns how many it added.
+///
+/// Does nothing without a server or a profile, the team needs to know who played.
+pub fn sync() -> io::Result<usize> {
+    let _syncing = SYNCING.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+
+    let mut config = TeamConfig::load();
+    let (Some(server), Some(player)) = (config.server.clone(), profile::current()) else {
+        return Ok(0);
+    };
+
+    let sessions: Vec<SessionRecord> = history::sessions()
+        .into_iter()
+        .filter(|session| !session.aborted && !session.imported)
+        .filter(|session| !config.synced.contains(&session.id()))
+        .map(|mut session| {
+            // The leaderboard only needs the results, trials stay on the player's machine
+            session.trials.clear();
+            session
+        })
+        .collect();
+    if sessions.is_empty() {
+        return Ok(0);
+    }
+    let sent: Vec<SessionId> = sessions.iter().map(SessionRecord::id).collect();
+
+    let request = Request::Submit {
+        player: player.name,
+        sessions,
+    };
+    match send(&server, &request)? {
+        Response::Submitted { added } => {
+            config.synced.extend(sent);
+            config.save()?;
+            Ok(added)
+        }
+        _ => Err(io::Error::new(io::ErrorKind::InvalidData, "unexpected answer from the server")),
+    }
+}
+
+/// Syncs without holding up the game, sessions that fail to go out are sent next time.
+pub fn sync_in_background() {
+    if TeamConfig::load().server.is_none() {
+        return;
+    }
+    thread::spawn(|| {
+        // Nobody to tell, the sessions stay in line for the next sync
+        let _ = sync();
+    });
+}
+
+/// Team rankings of every mode of a game.
+pub fn leaderboard(server: &str, game: &str, ranking: Ranking) -> io::Result<Vec<ModeBoard>> {
+    let request = Request::Leaderboard {
+        game: game.to_string(),
+        ranking,
+        limit: LEADERBOARD_SIZE,
+    };
+    match send(server, &request)? {
+        Response::Leaderboard { modes } => Ok(modes),
+        _ => Err(io::Error::new(io::ErrorKind::InvalidData, "unexpected answer from the server")),
+    }
+}
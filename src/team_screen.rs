@@ -0,0 +1,240 @@
+use crate::games::Game;
+use crate::keymap::{Action, KeyMap};
+use crate::profile;
+use crate::stats_screen::StatsGame;
+use crate::team::{self, ModeBoard, TeamConfig};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::layout::{Alignment, Constraint, Direction, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Cell, Paragraph, Row, Table};
+use ratatui::Frame;
+
+/// Team leaderboard fetched from the LAN server, run by the engine like a game.
+///
+/// Shows the best run of each player in one mode of one game at a time.
+pub struct TeamScreen {
+    games: Vec<StatsGame>,
+    game_index: usize,
+    config: TeamConfig,
+    boards: Vec<ModeBoard>,     // Every mode of the selected game, most played first
+    mode_index: usize,
+    player: Option<String>,     // Name of the profile, to pick out its row
+    typing: Option<String>,     // Server address being typed
+    message: String,            // Outcome of the last exchange with the server
+    quit: bool,
+}
+
+impl TeamScreen {
+    pub fn new(games: Vec<StatsGame>) -> Self {
+        let mut screen = Self {
+            games,
+            game_index: 0,
+            config: TeamConfig::load(),
+            boards: Vec::new(),
+            mode_index: 0,
+            player: profile::current().map(|profile| profile.name),
+            typing: None,
+            message: String::new(),
+            quit: false,
+        };
+        screen.refresh();
+        screen
+    }
+
+    /// Sends the sessions the server is missing, then fetches the selected game again.
+    ///
+    /// Waits for the server, which answers at once on a LAN or times out in a couple of seconds.
+    fn refresh(&mut self) {
+        self.boards.clear();
+        self.mode_index = 0;
+        let (Some(server), Some(game)) = (self.config.server.clone(), self.games.get(self.game_index)) else {
+            self.message = "No team server yet, press c to connect to one".to_string();
+            return;
+        };
+
+        let sent = match team::sync() {
+            Ok(0) => String::new(),
+            Ok(added) => format!("{added} new sessions sent • "),
+            Err(err) => format!("Sending failed: {err} • "),
+        };
+        self.message = match team::leaderboard(&server, &game.id, game.ranking) {
+            Ok(boards) => {
+                self.boards = boards;
+                format!("{sent}Connected to {server}")
+            }
+            Err(err) => format!("{sent}Can't reach {server}: {err}"),
+        };
+        // Sessions sent just now moved the sync point
+        self.config = TeamConfig::load();
+    }
+
+    fn select_game(&mut self, index: usize) {
+        self.game_index = index;
+        self.refresh();
+    }
+
+    fn handle_typing(&mut self, key_event: KeyEvent) {
+        let Some(address) = self.typing.as_mut() else {
+            return;
+        };
+
+        match key_event.code {
+            KeyCode::Enter => {
+                let address = address.trim().to_string();
+                // The history goes to a new server from the start
+                self.config = TeamConfig {
+                    server: (!address.is_empty()).then_some(address),
+                    ..TeamConfig::default()
+                };
+                self.typing = None;
+                match self.config.save() {
+                    Ok(()) => self.refresh(),
+                    Err(err) => self.message = format!("Can't save the address: {err}"),
+                }
+            }
+            KeyCode::Esc => self.typing = None,
+            KeyCode::Backspace => {
+                address.pop();
+            }
+            KeyCode::Char(c) if !key_event.modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) => {
+                address.push(c);
+            }
+            _ => {}
+        }
+    }
+
+    fn render_header(&self, frame: &mut Frame, area: Rect, game: &StatsGame) {
+        let bold = Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD);
+        let mode = match self.boards.get(self.mode_index) {
+            Some(board) => format!("Mode: ▲ {} ▼", board.mode.replace('_', " ")),
+            None => "No team runs yet".to_string(),
+        };
+        let server = match (&self.typing, &self.config.server) {
+            (Some(address), _) => format!("Server: {address}▏"),
+            (None, Some(server)) => format!("Server: {server}"),
+            (None, None) => "Server: none".to_string(),
+        };
+        let lines = vec![
+            Line::from(Span::styled(format!("◀ {} ▶", game.name), bold)),
+            Line::from(mode),
+            Line::from(server),
+            Line::styled(self.message.clone(), Style::default().fg(Color::Gray)),
+        ];
+        frame.render_widget(Paragraph::new(lines).alignment(Alignment::Center), area);
+    }
+
+    fn render_board(&self, frame: &mut Frame, area: Rect, board: &ModeBoard, game: &StatsGame) {
+        let rows = board.entries.iter().enumerate().map(|(rank, entry)| {
+            let style = if self.player.as_deref() == Some(entry.player.as_str()) {
+                Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
+            } else {
+                Style::default()
+            };
+            Row::new(vec![
+                Cell::from(format!("{}.", rank + 1)),
+                Cell::from(entry.player.clone()),
+                Cell::from(format!("{} {}", entry.session.score, game.unit)),
+                Cell::from(entry.session.played_at.format("%Y-%m-%d %H:%M").to_string()),
+            ])
+            .style(style)
+        });
+
+        let table = Table::new(
+            rows,
+            [Constraint::Length(4), Constraint::Min(12), Constraint::Length(14), Constraint::Length(17)],
+        )
+        .header(Row::new(vec!["#", "Player", "Best", "Played"]).style(Style::default().fg(Color::Gray)))
+        .block(Block::default().title(" 🏆 Team leaderboard ").borders(Borders::ALL));
+        frame.render_widget(table, area);
+    }
+}
+
+impl Game for TeamScreen {
+    fn name(&self) -> &str {
+        "🏆 Team"
+    }
+
+    fn description(&self) -> &str {
+        "Best runs of everyone on the team server"
+    }
+
+    fn id(&self) -> &str {
+        "team"
+    }
+
+    fn help(&self) -> &str {
+        "Finished runs are sent to the team server on the LAN, see `rataplay serve`.\n\
+         The leaderboard shows the best run of each player, per game and mode.\n\
+         \n\
+         left/right: game • up/down: mode • c: server address • r: refresh\n\
+         Back: return to the menu"
+    }
+
+    fn handle_input(&mut self, key_event: KeyEvent, keys: &KeyMap) {
+        if self.typing.is_some() {
+            self.handle_typing(key_event);
+            return;
+        }
+
+        let games = self.games.len().max(1);
+        let modes = self.boards.len().max(1);
+        let among = [Action::Left, Action::Right, Action::Up, Action::Down, Action::Back];
+        match keys.action(&key_event, &among) {
+            Some(Action::Left) => self.select_game((self.game_index + games - 1) % games),
+            Some(Action::Right) => self.select_game((self.game_index + 1) % games),
+            Some(Action::Up) => self.mode_index = (self.mode_index + modes - 1) % modes,
+            Some(Action::Down) => self.mode_index = (self.mode_index + 1) % modes,
+            Some(_) => self.quit = true,
+            None if key_event.code == KeyCode::Char('c') => {
+                self.typing = Some(self.config.server.clone().unwrap_or_default());
+            }
+            None if key_event.code == KeyCode::Char('r') => self.refresh(),
+            None => {}
+        }
+    }
+
+    fn render(&self, frame: &mut Frame) {
+        let layout = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(5), Constraint::Min(8), Constraint::Length(1)])
+            .split(frame.area());
+
+        let Some(game) = self.games.get(self.game_index) else {
+            return;
+        };
+        self.render_header(frame, layout[0], game);
+
+        match self.boards.get(self.mode_index) {
+            Some(board) => self.render_board(frame, layout[1], board, game),
+            None => frame.render_widget(
+                Paragraph::new("Nobody on the team finished a run of this game yet")
+                    .alignment(Alignment::Center)
+                    .block(Block::default().borders(Borders::ALL)),
+                layout[1],
+            ),
+        }
+
+        let hint = if self.typing.is_some() {
+            "Type host or host:port • Enter to save, empty to stop sending • Esc to cancel"
+        } else {
+            "left/right: game • up/down: mode • c: server • r: refresh • Back: return to the menu"
+        };
+        let hint = Paragraph::new(hint)
+            .style(Style::default().fg(Color::Gray))
+            .alignment(Alignment::Center);
+        frame.render_widget(hint, layout[2]);
+    }
+
+    fn wants_exit(&self) -> bool {
+        self.quit
+    }
+
+    fn restart(&mut self) {
+        self.refresh();
+    }
+
+    fn on_exit(&mut self) {
+        self.quit = false;
+    }
+}